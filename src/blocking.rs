@@ -0,0 +1,426 @@
+use crate::{
+    KeysPage, KvClient, KvError, KvNamespaceClient, KvRequest, ListOptions, Namespace,
+    StorageStats, WriteOptions,
+};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+
+fn new_runtime() -> Result<Arc<Runtime>, KvError> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(KvError::Runtime)?;
+    Ok(Arc::new(runtime))
+}
+
+/// Blocking counterpart of `KvClient`, driving each call on an internal runtime.
+///
+/// Like `reqwest::blocking`, it must not be used from within an async runtime.
+#[derive(Clone, Debug)]
+pub struct KvClientBlocking {
+    inner: KvClient,
+    runtime: Arc<Runtime>,
+}
+
+impl KvClientBlocking {
+    pub fn new(account_id: &str, api_key: &str) -> Self {
+        Self::from_async(KvClient::new(account_id, api_key))
+    }
+
+    pub fn try_new(account_id: &str, api_key: &str) -> Result<Self, KvError> {
+        Self::try_from_async(KvClient::try_new(account_id, api_key)?)
+    }
+
+    pub fn from_env() -> Result<Self, KvError> {
+        Self::try_from_async(KvClient::from_env()?)
+    }
+
+    pub fn from_async(client: KvClient) -> Self {
+        Self::try_from_async(client).unwrap()
+    }
+
+    /// Like `from_async`, but returns an error instead of panicking if the internal
+    /// runtime cannot be started.
+    pub fn try_from_async(client: KvClient) -> Result<Self, KvError> {
+        Ok(KvClientBlocking {
+            inner: client,
+            runtime: new_runtime()?,
+        })
+    }
+
+    pub fn verify(&self) -> Result<(), KvError> {
+        self.runtime.block_on(self.inner.verify())
+    }
+
+    pub fn warm_up(&self) -> Result<(), KvError> {
+        self.runtime.block_on(self.inner.warm_up())
+    }
+
+    pub fn list_namespaces(&self) -> Result<Vec<Namespace>, KvError> {
+        self.runtime.block_on(self.inner.list_namespaces())
+    }
+
+    pub fn get_namespace_by_title(&self, title: &str) -> Result<Option<Namespace>, KvError> {
+        self.runtime
+            .block_on(self.inner.get_namespace_by_title(title))
+    }
+
+    pub fn find_namespaces(
+        &self,
+        contains: &str,
+        case_insensitive: bool,
+    ) -> Result<Vec<Namespace>, KvError> {
+        self.runtime
+            .block_on(self.inner.find_namespaces(contains, case_insensitive))
+    }
+
+    pub fn get_or_create_namespace(&self, title: &str) -> Result<Namespace, KvError> {
+        self.runtime
+            .block_on(self.inner.get_or_create_namespace(title))
+    }
+
+    pub fn create_namespace(&self, title: &str) -> Result<Namespace, KvError> {
+        self.runtime.block_on(self.inner.create_namespace(title))
+    }
+
+    pub fn create_namespace_client(
+        &self,
+        title: &str,
+    ) -> Result<KvNamespaceClientBlocking, KvError> {
+        let client = self
+            .runtime
+            .block_on(self.inner.create_namespace_client(title))?;
+        Ok(KvNamespaceClientBlocking {
+            inner: client,
+            runtime: self.runtime.clone(),
+        })
+    }
+
+    pub fn namespace_client_by_title(
+        &self,
+        title: &str,
+    ) -> Result<KvNamespaceClientBlocking, KvError> {
+        let client = self
+            .runtime
+            .block_on(self.inner.namespace_client_by_title(title))?;
+        Ok(KvNamespaceClientBlocking {
+            inner: client,
+            runtime: self.runtime.clone(),
+        })
+    }
+
+    pub fn delete_namespace_by_title(&self, title: &str) -> Result<(), KvError> {
+        self.runtime
+            .block_on(self.inner.delete_namespace_by_title(title))
+    }
+
+    pub fn namespace_client(
+        &self,
+        namespace_id: &str,
+    ) -> Result<KvNamespaceClientBlocking, KvError> {
+        Ok(KvNamespaceClientBlocking {
+            inner: KvNamespaceClient::from_kvclient(&self.inner, namespace_id)?,
+            runtime: self.runtime.clone(),
+        })
+    }
+}
+
+/// Blocking counterpart of `KvNamespaceClient`, driving each call on an internal runtime.
+///
+/// Like `reqwest::blocking`, it must not be used from within an async runtime.
+#[derive(Clone, Debug)]
+pub struct KvNamespaceClientBlocking {
+    inner: KvNamespaceClient,
+    runtime: Arc<Runtime>,
+}
+
+impl KvNamespaceClientBlocking {
+    pub fn new(account_id: &str, api_key: &str, namespace_id: &str) -> Self {
+        Self::from_async(KvNamespaceClient::new(account_id, api_key, namespace_id))
+    }
+
+    pub fn try_new(account_id: &str, api_key: &str, namespace_id: &str) -> Result<Self, KvError> {
+        Self::try_from_async(KvNamespaceClient::try_new(
+            account_id,
+            api_key,
+            namespace_id,
+        )?)
+    }
+
+    pub fn from_url(url: &str) -> Result<Self, KvError> {
+        Self::try_from_async(KvNamespaceClient::from_url(url)?)
+    }
+
+    pub fn from_async(client: KvNamespaceClient) -> Self {
+        Self::try_from_async(client).unwrap()
+    }
+
+    /// Like `from_async`, but returns an error instead of panicking if the internal
+    /// runtime cannot be started.
+    pub fn try_from_async(client: KvNamespaceClient) -> Result<Self, KvError> {
+        Ok(KvNamespaceClientBlocking {
+            inner: client,
+            runtime: new_runtime()?,
+        })
+    }
+
+    pub fn delete_namespace(&self) -> Result<(), KvError> {
+        self.runtime.block_on(self.inner.delete_namespace())
+    }
+
+    pub fn get_namespace_info(&self) -> Result<Namespace, KvError> {
+        self.runtime.block_on(self.inner.get_namespace_info())
+    }
+
+    pub fn storage_usage(&self) -> Result<StorageStats, KvError> {
+        self.runtime.block_on(self.inner.storage_usage())
+    }
+
+    pub fn rename_namespace(&self, new_title: &str) -> Result<Namespace, KvError> {
+        self.runtime
+            .block_on(self.inner.rename_namespace(new_title))
+    }
+
+    pub fn write(&self, payload: KvRequest) -> Result<(), KvError> {
+        self.runtime.block_on(self.inner.write(payload))
+    }
+
+    pub fn write_streaming(
+        &self,
+        key: impl AsRef<str>,
+        body: impl Into<reqwest::Body>,
+    ) -> Result<(), KvError> {
+        self.runtime.block_on(self.inner.write_streaming(key, body))
+    }
+
+    pub fn put(
+        &self,
+        key: impl AsRef<str>,
+        value: impl Into<Vec<u8>>,
+        opts: WriteOptions,
+    ) -> Result<(), KvError> {
+        self.runtime.block_on(self.inner.put(key, value, opts))
+    }
+
+    pub fn put_with_content_type(
+        &self,
+        key: impl AsRef<str>,
+        value: impl Into<Vec<u8>>,
+        content_type: &str,
+        opts: WriteOptions,
+    ) -> Result<(), KvError> {
+        self.runtime.block_on(
+            self.inner
+                .put_with_content_type(key, value, content_type, opts),
+        )
+    }
+
+    pub fn write_with_ttl(
+        &self,
+        key: impl AsRef<str>,
+        value: &str,
+        ttl_sec: u64,
+    ) -> Result<(), KvError> {
+        self.runtime
+            .block_on(self.inner.write_with_ttl(key, value, ttl_sec))
+    }
+
+    pub fn write_if_absent(&self, payload: KvRequest) -> Result<bool, KvError> {
+        self.runtime.block_on(self.inner.write_if_absent(payload))
+    }
+
+    pub fn write_multiple(&self, payload: Vec<KvRequest>) -> Result<(), KvError> {
+        self.runtime.block_on(self.inner.write_multiple(payload))
+    }
+
+    pub fn write_multiple_dedup(&self, payload: Vec<KvRequest>) -> Result<(), KvError> {
+        self.runtime
+            .block_on(self.inner.write_multiple_dedup(payload))
+    }
+
+    pub fn write_pairs(
+        &self,
+        pairs: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
+    ) -> Result<(), KvError> {
+        self.runtime.block_on(self.inner.write_pairs(pairs))
+    }
+
+    pub fn delete(&self, key: impl AsRef<str>) -> Result<(), KvError> {
+        self.runtime.block_on(self.inner.delete(key))
+    }
+
+    pub fn delete_checked(&self, key: impl AsRef<str>) -> Result<bool, KvError> {
+        self.runtime.block_on(self.inner.delete_checked(key))
+    }
+
+    pub fn delete_multiple(
+        &self,
+        keys: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> Result<(), KvError> {
+        self.runtime.block_on(self.inner.delete_multiple(keys))
+    }
+
+    pub fn delete_many_individually(&self, keys: &[&str]) -> Vec<(String, Result<(), KvError>)> {
+        self.runtime
+            .block_on(self.inner.delete_many_individually(keys))
+    }
+
+    pub fn list_keys_page(&self, opts: ListOptions) -> Result<KeysPage, KvError> {
+        self.runtime.block_on(self.inner.list_keys_page(opts))
+    }
+
+    pub fn list_all_keys(&self) -> Result<Vec<String>, KvError> {
+        self.runtime.block_on(self.inner.list_all_keys())
+    }
+
+    pub fn list_all_keys_cached(&self, ttl: std::time::Duration) -> Result<Vec<String>, KvError> {
+        self.runtime.block_on(self.inner.list_all_keys_cached(ttl))
+    }
+
+    pub fn list_all_keys_with_count(&self) -> Result<(Vec<String>, u64), KvError> {
+        self.runtime.block_on(self.inner.list_all_keys_with_count())
+    }
+
+    pub fn count_keys(&self) -> Result<u64, KvError> {
+        self.runtime.block_on(self.inner.count_keys())
+    }
+
+    pub fn clear(&self) -> Result<usize, KvError> {
+        self.runtime.block_on(self.inner.clear())
+    }
+
+    pub fn clear_with_progress(&self, progress: impl Fn(usize, usize)) -> Result<usize, KvError> {
+        self.runtime
+            .block_on(self.inner.clear_with_progress(progress))
+    }
+
+    pub fn clear_prefix(&self, prefix: &str) -> Result<usize, KvError> {
+        self.runtime.block_on(self.inner.clear_prefix(prefix))
+    }
+
+    pub fn copy_all_to(&self, dest: &KvNamespaceClientBlocking) -> Result<usize, KvError> {
+        self.runtime.block_on(self.inner.copy_all_to(&dest.inner))
+    }
+
+    pub fn copy_all_to_with_progress(
+        &self,
+        dest: &KvNamespaceClientBlocking,
+        progress: impl Fn(usize, usize),
+    ) -> Result<usize, KvError> {
+        self.runtime
+            .block_on(self.inner.copy_all_to_with_progress(&dest.inner, progress))
+    }
+
+    pub fn read_metadata(&self, key: impl AsRef<str>) -> Result<Value, KvError> {
+        self.runtime.block_on(self.inner.read_metadata(key))
+    }
+
+    pub fn read_metadata_many(&self, keys: &[&str]) -> Vec<(String, Result<Value, KvError>)> {
+        self.runtime.block_on(self.inner.read_metadata_many(keys))
+    }
+
+    pub fn get_content_type(&self, key: impl AsRef<str>) -> Result<Option<String>, KvError> {
+        self.runtime.block_on(self.inner.get_content_type(key))
+    }
+
+    pub fn read_metadata_as<T: DeserializeOwned>(
+        &self,
+        key: impl AsRef<str>,
+    ) -> Result<T, KvError> {
+        self.runtime.block_on(self.inner.read_metadata_as(key))
+    }
+
+    pub fn metadata_u64(&self, key: impl AsRef<str>, field: &str) -> Result<Option<u64>, KvError> {
+        self.runtime.block_on(self.inner.metadata_u64(key, field))
+    }
+
+    pub fn exists(&self, key: impl AsRef<str>) -> Result<bool, KvError> {
+        self.runtime.block_on(self.inner.exists(key))
+    }
+
+    pub fn exists_multiple(&self, keys: &[&str]) -> Result<HashMap<String, bool>, KvError> {
+        self.runtime.block_on(self.inner.exists_multiple(keys))
+    }
+
+    pub fn get(&self, key: impl AsRef<str>) -> Result<String, KvError> {
+        self.runtime.block_on(self.inner.get(key))
+    }
+
+    pub fn get_with_timeout(
+        &self,
+        key: impl AsRef<str>,
+        timeout: std::time::Duration,
+    ) -> Result<String, KvError> {
+        self.runtime
+            .block_on(self.inner.get_with_timeout(key, timeout))
+    }
+
+    pub fn get_json<T: DeserializeOwned>(&self, key: impl AsRef<str>) -> Result<T, KvError> {
+        self.runtime.block_on(self.inner.get_json(key))
+    }
+
+    pub fn get_many(&self, keys: &[&str]) -> Vec<(String, Result<String, KvError>)> {
+        self.runtime.block_on(self.inner.get_many(keys))
+    }
+
+    pub fn get_many_with_progress(
+        &self,
+        keys: &[&str],
+        progress: impl Fn(usize, usize),
+    ) -> Vec<(String, Result<String, KvError>)> {
+        self.runtime
+            .block_on(self.inner.get_many_with_progress(keys, progress))
+    }
+
+    pub fn get_many_json<T: DeserializeOwned>(
+        &self,
+        keys: &[&str],
+    ) -> Vec<(String, Result<T, KvError>)> {
+        self.runtime.block_on(self.inner.get_many_json(keys))
+    }
+
+    pub fn get_if_none_match(
+        &self,
+        key: impl AsRef<str>,
+        etag: &str,
+    ) -> Result<Option<(String, String)>, KvError> {
+        self.runtime
+            .block_on(self.inner.get_if_none_match(key, etag))
+    }
+
+    pub fn get_bytes(&self, key: impl AsRef<str>) -> Result<Vec<u8>, KvError> {
+        self.runtime.block_on(self.inner.get_bytes(key))
+    }
+
+    pub fn update_metadata(&self, key: impl AsRef<str>, metadata: Value) -> Result<(), KvError> {
+        self.runtime
+            .block_on(self.inner.update_metadata(key, metadata))
+    }
+
+    pub fn get_with_metadata(
+        &self,
+        key: impl AsRef<str>,
+    ) -> Result<(String, Option<Value>), KvError> {
+        self.runtime.block_on(self.inner.get_with_metadata(key))
+    }
+
+    pub fn get_with_expiration(
+        &self,
+        key: impl AsRef<str>,
+    ) -> Result<(String, Option<u64>), KvError> {
+        self.runtime.block_on(self.inner.get_with_expiration(key))
+    }
+
+    pub fn put_raw_bytes(
+        &self,
+        key: impl AsRef<str>,
+        bytes: &[u8],
+        opts: WriteOptions,
+    ) -> Result<(), KvError> {
+        self.runtime
+            .block_on(self.inner.put_raw_bytes(key, bytes, opts))
+    }
+}
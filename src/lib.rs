@@ -1,30 +1,588 @@
-use log::warn;
-use reqwest::header::HeaderMap;
-use reqwest::Client;
+use base64::Engine;
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+use log::{debug, log_enabled, warn, Level};
+use percent_encoding::{
+    percent_decode_str, utf8_percent_encode, AsciiSet, PercentEncode, NON_ALPHANUMERIC,
+};
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, CONTENT_TYPE, ETAG, IF_NONE_MATCH,
+};
+use reqwest::{Client, StatusCode};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::time::Duration;
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{SystemTime, UNIX_EPOCH};
+#[cfg(feature = "cancellation")]
+use tokio_util::sync::CancellationToken;
+
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(feature = "test-util")]
+pub mod mock;
 
 const CF_API_URL: &str = "https://api.cloudflare.com/client/v4/";
+const DEFAULT_USER_AGENT: &str =
+    concat!("cloudflare-workers-kv-sdk-rs/", env!("CARGO_PKG_VERSION"));
+const DEFAULT_CONCURRENCY: usize = 4;
+const MAX_ERROR_BODY_LEN: usize = 2048;
+const MAX_LOGGED_BODY_LEN: usize = 4096;
+const BULK_LIMIT: usize = 10_000;
+const NAMESPACES_PER_PAGE: usize = 100;
+const NAMESPACE_EXISTS_CODE: u64 = 10014;
+const DEFAULT_MAX_RETRIES: u32 = 3;
+#[cfg(not(target_arch = "wasm32"))]
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(30);
+const MIN_EXPIRATION_TTL: u64 = 60;
+const CONTENT_TYPE_FIELD: &str = "content_type";
+const DEFAULT_MAX_PAGES: usize = 100_000;
+#[cfg(not(target_arch = "wasm32"))]
+const STORAGE_USAGE_WINDOW: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+#[cfg(not(target_arch = "wasm32"))]
+const STORAGE_USAGE_QUERY: &str =
+    "query ($accountTag: string!, $namespaceId: string!, $since: Date!) {
+  viewer {
+    accounts(filter: { accountTag: $accountTag }) {
+      kvStorageAdaptiveGroups(
+        filter: { namespaceId: $namespaceId, date_geq: $since }
+        limit: 1
+        orderBy: [date_DESC]
+      ) {
+        max { keyCount byteCount }
+        dimensions { date }
+      }
+    }
+  }
+}";
+const MAX_KEY_LEN: usize = 512;
+const MAX_VALUE_LEN: usize = 25 * 1024 * 1024;
+const MAX_METADATA_LEN: usize = 1024;
+
+/// Environment variable `KvClient::from_env` reads the account id from.
+pub const ACCOUNT_ID_ENV: &str = "CLOUDFLARE_ACCOUNT_ID";
+/// Environment variable `KvClient::from_env` reads the API token from.
+pub const API_TOKEN_ENV: &str = "CLOUDFLARE_API_TOKEN";
+
+#[derive(Debug)]
+pub enum KvError {
+    Request(reqwest::Error),
+    Api {
+        status: u16,
+        body: String,
+    },
+    MalformedResponse(String),
+    Serialize(serde_json::Error),
+    Deserialize(serde_json::Error),
+    WriteRejected(String),
+    UnexpectedType(String),
+    ChunksFailed(Vec<(usize, KvError)>),
+    InvalidExpiration(String),
+    InvalidHeader(String),
+    InvalidNamespaceId(String),
+    MissingEnvVar(String),
+    NamespaceNotFound {
+        title: String,
+    },
+    Unauthorized {
+        status: u16,
+        body: String,
+    },
+    NotFound {
+        key: String,
+    },
+    InvalidRequest(String),
+    NamespaceExists {
+        title: String,
+    },
+    Timeout(Duration),
+    Pagination(String),
+    PermissionDenied {
+        operation: String,
+        status: u16,
+        body: String,
+    },
+    InvalidUrl(String),
+    BatchValidation(Vec<(usize, KvError)>),
+    Runtime(std::io::Error),
+}
+
+impl std::fmt::Display for KvError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KvError::Request(e) => write!(f, "HTTP request to Cloudflare failed: {}", e),
+            KvError::Api { status, body } => match api_error_messages(body) {
+                Some(messages) => write!(f, "Cloudflare API error (HTTP {}): {}", status, messages),
+                None if body.len() > MAX_ERROR_BODY_LEN => write!(
+                    f,
+                    "Cloudflare API error (HTTP {}): {}...(truncated)",
+                    status,
+                    truncate_at_char_boundary(body, MAX_ERROR_BODY_LEN)
+                ),
+                None => write!(f, "Cloudflare API error (HTTP {}): {}", status, body),
+            },
+            KvError::MalformedResponse(msg) => {
+                write!(f, "Malformed response from Cloudflare: {}", msg)
+            }
+            KvError::Serialize(e) => write!(f, "Failed to serialize value: {}", e),
+            KvError::Deserialize(e) => write!(f, "Failed to deserialize value: {}", e),
+            KvError::WriteRejected(msg) => write!(f, "Write rejected: {}", msg),
+            KvError::UnexpectedType(msg) => write!(f, "Unexpected type: {}", msg),
+            KvError::InvalidExpiration(msg) => write!(f, "Invalid expiration: {}", msg),
+            KvError::InvalidHeader(msg) => write!(f, "Invalid header: {}", msg),
+            KvError::InvalidNamespaceId(id) => write!(
+                f,
+                "Invalid namespace id '{}': expected a 32-character hex string",
+                id
+            ),
+            KvError::MissingEnvVar(name) => {
+                write!(f, "Environment variable '{}' is not set", name)
+            }
+            KvError::NamespaceNotFound { title } => {
+                write!(f, "No namespace titled '{}' exists", title)
+            }
+            KvError::Unauthorized { status, .. } => write!(
+                f,
+                "Cloudflare rejected the credentials (HTTP {}): check the account id and API token",
+                status
+            ),
+            KvError::NotFound { key } => write!(f, "Key '{}' not found", key),
+            KvError::InvalidRequest(msg) => write!(f, "Invalid request: {}", msg),
+            KvError::NamespaceExists { title } => {
+                write!(f, "A namespace titled '{}' already exists", title)
+            }
+            KvError::Timeout(timeout) => write!(f, "Operation timed out after {:?}", timeout),
+            KvError::Pagination(msg) => write!(f, "Key listing aborted: {}", msg),
+            KvError::InvalidUrl(msg) => write!(f, "Invalid connection URL: {}", msg),
+            KvError::PermissionDenied { operation, .. } => write!(
+                f,
+                "Cloudflare denied permission to {}: the API token may be read-only",
+                operation
+            ),
+            KvError::Runtime(e) => write!(f, "Failed to start the blocking runtime: {}", e),
+            KvError::BatchValidation(failures) => {
+                write!(f, "{} request(s) failed validation:", failures.len())?;
+                for (index, e) in failures {
+                    write!(f, " [request {}: {}]", index, e)?;
+                }
+                Ok(())
+            }
+            KvError::ChunksFailed(failures) => {
+                write!(f, "{} bulk write chunk(s) failed:", failures.len())?;
+                for (index, e) in failures {
+                    write!(f, " [chunk {}: {}]", index, e)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+// Cloudflare reports failures in `errors`, but sometimes only in `messages`, whose
+// entries may be plain strings instead of `{ code, message }` objects.
+fn api_error_messages(body: &str) -> Option<String> {
+    let resp_json = serde_json::from_str::<Value>(body).ok()?;
+    let messages = ["errors", "messages"]
+        .iter()
+        .filter_map(|field| resp_json.get(field)?.as_array())
+        .flatten()
+        .filter_map(|error| {
+            if let Some(message) = error.as_str() {
+                return Some(message.to_string());
+            }
+            let message = error.get("message")?.as_str()?;
+            match error.get("code").and_then(|code| code.as_u64()) {
+                Some(code) => Some(format!("{} (code {})", message, code)),
+                None => Some(message.to_string()),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if messages.is_empty() {
+        None
+    } else {
+        Some(messages.join("; "))
+    }
+}
+
+impl KvError {
+    /// Returns the full response body of an API error, which `Display` truncates.
+    pub fn body(&self) -> Option<&str> {
+        match self {
+            KvError::Api { body, .. }
+            | KvError::Unauthorized { body, .. }
+            | KvError::PermissionDenied { body, .. } => Some(body),
+            _ => None,
+        }
+    }
+
+    /// Returns the HTTP status code Cloudflare answered with, if the error came from the API.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            KvError::Api { status, .. }
+            | KvError::Unauthorized { status, .. }
+            | KvError::PermissionDenied { status, .. } => Some(*status),
+            KvError::NotFound { .. } => Some(404),
+            _ => None,
+        }
+    }
+
+    /// Returns whether retrying the operation may succeed: true for 429 and 5xx answers,
+    /// timeouts and connection failures, false for other 4xx answers, validation errors
+    /// and missing keys.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            KvError::Request(e) => {
+                #[cfg(not(target_arch = "wasm32"))]
+                if e.is_connect() {
+                    return true;
+                }
+                e.is_timeout() || e.is_request()
+            }
+            KvError::Api { status, .. } => *status == 429 || *status >= 500,
+            KvError::Timeout(_) => true,
+            _ => false,
+        }
+    }
+
+    // Read-only tokens get a 403 on writes, which would otherwise surface as a generic
+    // API error. Code 10000 alone isn't enough, since invalid tokens get it too.
+    fn denied_as(self, operation: &str) -> KvError {
+        match self {
+            KvError::Api { status: 403, body } => KvError::PermissionDenied {
+                operation: operation.to_string(),
+                status: 403,
+                body,
+            },
+            e => e,
+        }
+    }
+
+    fn has_error_code(&self, code: u64) -> bool {
+        let body = match self {
+            KvError::Api { body, .. } => body,
+            _ => return false,
+        };
+
+        match serde_json::from_str::<Value>(body) {
+            Ok(resp_json) => match resp_json.get("errors").and_then(|errors| errors.as_array()) {
+                Some(errors) => errors
+                    .iter()
+                    .any(|error| error.get("code").and_then(|c| c.as_u64()) == Some(code)),
+                None => false,
+            },
+            Err(_) => false,
+        }
+    }
+}
+
+impl std::error::Error for KvError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            KvError::Request(e) => Some(e),
+            KvError::Serialize(e) | KvError::Deserialize(e) => Some(e),
+            KvError::Runtime(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for KvError {
+    fn from(e: reqwest::Error) -> Self {
+        KvError::Request(e)
+    }
+}
+
+// The wasm32 backend of reqwest runs on the host's `fetch`, which has no
+// connection-level settings. With the `gzip`, `deflate` or `brotli` features
+// enabled, reqwest negotiates and decodes compressed responses on its own.
+fn default_client_builder() -> reqwest::ClientBuilder {
+    #[cfg(not(target_arch = "wasm32"))]
+    let client_builder = Client::builder()
+        .user_agent(DEFAULT_USER_AGENT)
+        .connect_timeout(DEFAULT_CONNECT_TIMEOUT);
+    #[cfg(target_arch = "wasm32")]
+    let client_builder = Client::builder().user_agent(DEFAULT_USER_AGENT);
+
+    client_builder
+}
+
+fn join_url(base: &str, segment: &str) -> String {
+    format!(
+        "{}/{}",
+        base.trim_end_matches('/'),
+        segment.trim_start_matches('/')
+    )
+}
+
+fn namespaces_url(account_id: &str) -> String {
+    join_url(
+        CF_API_URL,
+        &format!(
+            "accounts/{}/storage/kv/namespaces",
+            account_id.trim_matches('/')
+        ),
+    )
+}
+
+fn record_status(status: u16) {
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("status", status);
+    #[cfg(not(feature = "tracing"))]
+    let _ = status;
+}
+
+// Everything except RFC 3986 unreserved characters is escaped, so `/`, `?`, `#` and
+// spaces in a key stay inside its path segment.
+const KEY_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+// Formats a Unix timestamp as a UTC `YYYY-MM-DD` date with Howard Hinnant's
+// `civil_from_days`, so the crate doesn't need a date library for it.
+#[cfg(not(target_arch = "wasm32"))]
+fn utc_date(timestamp: u64) -> String {
+    let days = (timestamp / 86_400) as i64 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = era * 400 + year_of_era + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// Keys are read well before their batch is written, so a key that was far enough from
+// expiring when read can be too close by the time `write_multiple` validates it.
+fn drop_expiring(batch: &mut Vec<KvRequest>) {
+    batch.retain(|request| validate_expiration(&request.key, request.expiration, None).is_ok());
+}
+
+fn validate_namespace_id(namespace_id: &str) -> Result<(), KvError> {
+    if namespace_id.len() != 32 || !namespace_id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(KvError::InvalidNamespaceId(namespace_id.to_string()));
+    }
+    Ok(())
+}
+
+fn encode_key(key: &str) -> PercentEncode<'_> {
+    utf8_percent_encode(key, KEY_ENCODE_SET)
+}
+
+fn default_header_map(api_key: &str) -> Result<HeaderMap, KvError> {
+    let authorization = HeaderValue::from_str(&format!("Bearer {}", api_key))
+        .map_err(|e| KvError::InvalidHeader(format!("Authorization: {}", e)))?;
+
+    Ok(HeaderMap::from_iter([
+        (AUTHORIZATION, authorization),
+        (CONTENT_TYPE, HeaderValue::from_static("application/json")),
+    ]))
+}
+
+fn global_key_header_map(email: &str, key: &str) -> Result<HeaderMap, KvError> {
+    let email = HeaderValue::from_str(email)
+        .map_err(|e| KvError::InvalidHeader(format!("X-Auth-Email: {}", e)))?;
+    let key = HeaderValue::from_str(key)
+        .map_err(|e| KvError::InvalidHeader(format!("X-Auth-Key: {}", e)))?;
+
+    Ok(HeaderMap::from_iter([
+        (HeaderName::from_static("x-auth-email"), email),
+        (HeaderName::from_static("x-auth-key"), key),
+        (CONTENT_TYPE, HeaderValue::from_static("application/json")),
+    ]))
+}
+
+fn expiration_header(resp: &reqwest::Response) -> Option<u64> {
+    resp.headers()
+        .get("expiration")
+        .and_then(|expiration| expiration.to_str().ok())
+        .and_then(|expiration| expiration.parse::<u64>().ok())
+}
+
+async fn check_value_response(
+    key: &str,
+    resp: reqwest::Response,
+) -> Result<reqwest::Response, KvError> {
+    if resp.status().as_u16() == 404 {
+        record_status(404);
+        return Err(KvError::NotFound {
+            key: key.to_string(),
+        });
+    }
+
+    if !resp.status().is_success() {
+        return Err(error_from_response(resp).await);
+    }
+
+    record_status(resp.status().as_u16());
 
-async fn convert_string_to_error(s: &str) -> Box<dyn std::error::Error> {
-    Box::new(std::io::Error::new(std::io::ErrorKind::Other, s))
+    Ok(resp)
 }
 
-async fn check_success(resp_json: Value) -> Result<bool, Box<dyn std::error::Error>> {
+async fn check_success(resp_json: &Value) -> Result<bool, KvError> {
     match resp_json.get("success") {
         Some(success) => match success.as_bool() {
             Some(true) => Ok(true),
             Some(false) => Ok(false),
-            None => Err(convert_string_to_error(
-                "The returned 'success' field is not a boolean value.",
-            )
-            .await),
+            None => Err(KvError::MalformedResponse(
+                "The returned 'success' field is not a boolean value.".to_string(),
+            )),
         },
-        None => Err(convert_string_to_error(
-            "The returned JSON does not contain the 'success' field.",
-        )
-        .await),
+        None => Err(KvError::MalformedResponse(
+            "The returned JSON does not contain the 'success' field.".to_string(),
+        )),
+    }
+}
+
+// Cuts `s` to at most `max_len` bytes without splitting a UTF-8 character.
+fn truncate_at_char_boundary(s: &str, max_len: usize) -> &str {
+    if s.len() <= max_len {
+        return s;
+    }
+    let mut end = max_len;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+// Only bodies are logged: credentials travel in headers, which are never logged.
+fn debug_body(label: &str, url: &str, body: impl std::fmt::Display) {
+    if !log_enabled!(Level::Debug) {
+        return;
+    }
+
+    let body = body.to_string();
+    let truncated = truncate_at_char_boundary(&body, MAX_LOGGED_BODY_LEN);
+    if truncated.len() < body.len() {
+        debug!("{} {}: {}...(truncated)", label, url, truncated);
+    } else {
+        debug!("{} {}: {}", label, url, body);
+    }
+}
+
+// Like `debug_body`, but only decodes a raw body when debug logging is on.
+fn debug_bytes(label: &str, url: &str, body: &[u8]) {
+    if log_enabled!(Level::Debug) {
+        debug_body(label, url, String::from_utf8_lossy(body));
+    }
+}
+
+async fn handle_response(resp: reqwest::Response) -> Result<Value, KvError> {
+    if !resp.status().is_success() {
+        return Err(error_from_response(resp).await);
+    }
+
+    let status = resp.status().as_u16();
+    record_status(status);
+
+    let url = resp.url().to_string();
+    let resp_json = resp.json::<Value>().await?;
+    debug_body(&format!("Response {} from", status), &url, &resp_json);
+
+    if !check_success(&resp_json).await? {
+        return Err(KvError::Api {
+            status,
+            body: resp_json.to_string(),
+        });
+    }
+
+    Ok(resp_json)
+}
+
+// `success` and `errors` are already checked by `handle_response`, so only the
+// payload is deserialized here.
+#[derive(Deserialize)]
+struct ApiResponse<T> {
+    result: T,
+    result_info: Option<ResultInfo>,
+}
+
+#[derive(Deserialize)]
+struct ResultInfo {
+    // Only key listings are cursor-paginated; namespace listings use pages.
+    #[serde(default)]
+    cursor: String,
+    count: u64,
+    #[serde(default)]
+    list_complete: Option<bool>,
+}
+
+impl ResultInfo {
+    // `list_complete` is authoritative when present; an empty cursor is the fallback,
+    // and also ends the listing since there is nothing to continue from.
+    fn is_complete(&self) -> bool {
+        self.list_complete == Some(true) || self.cursor.is_empty()
+    }
+
+    // The cursor of the next page, empty once the listing is complete.
+    fn next_cursor(self) -> String {
+        if self.is_complete() {
+            String::new()
+        } else {
+            self.cursor
+        }
+    }
+}
+
+// Fetches namespace pages from page 1 until one comes back shorter than a full page.
+async fn collect_namespace_pages<F, Fut>(mut fetch_page: F) -> Result<Vec<Namespace>, KvError>
+where
+    F: FnMut(usize) -> Fut,
+    Fut: Future<Output = Result<Vec<Namespace>, KvError>>,
+{
+    let mut namespaces = Vec::new();
+    let mut page = 1;
+    loop {
+        let page_namespaces = fetch_page(page).await?;
+        let page_len = page_namespaces.len();
+        namespaces.extend(page_namespaces);
+
+        if page_len < NAMESPACES_PER_PAGE {
+            break;
+        }
+        page += 1;
+    }
+    Ok(namespaces)
+}
+
+fn parse_response<T: DeserializeOwned>(resp_json: Value) -> Result<ApiResponse<T>, KvError> {
+    serde_json::from_value(resp_json).map_err(|e| KvError::MalformedResponse(e.to_string()))
+}
+
+async fn error_from_response(resp: reqwest::Response) -> KvError {
+    let status = resp.status().as_u16();
+    record_status(status);
+
+    // Callers that expect a status (404 from `get`/`exists`, 304 from
+    // `get_if_none_match`) handle it before getting here, so this only fires for
+    // genuine failures.
+    let url = resp.url().to_string();
+    warn!("Cloudflare returned HTTP {} for {}", status, url);
+
+    match resp.text().await {
+        Ok(body) => {
+            debug_body(&format!("Response {} from", status), &url, &body);
+            KvError::Api { status, body }
+        }
+        Err(e) => KvError::Request(e),
     }
 }
 
@@ -34,517 +592,2416 @@ pub struct KvClient {
     pub api_key: String,
     client: Client,
     url: String,
-    header_map: HeaderMap,
+    header_map: Arc<HeaderMap>,
+    retry_policy: Arc<dyn RetryPolicy>,
+    bulk_concurrency: usize,
+    max_pages: usize,
+    observer: Option<Arc<dyn KvObserver>>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Namespace {
     pub id: String,
     pub title: String,
 }
 
+impl std::fmt::Debug for KvClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KvClient")
+            .field("account_id", &self.account_id)
+            .field("api_key", &"[REDACTED]")
+            .field("url", &self.url)
+            .finish()
+    }
+}
+
 impl KvClient {
+    async fn send(
+        &self,
+        op: &str,
+        key: Option<&str>,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, KvError> {
+        send_observed(self.observer.as_deref(), op, key, request).await
+    }
+
+    pub fn builder(account_id: &str, api_key: &str) -> KvClientBuilder {
+        KvClientBuilder::new(account_id, api_key)
+    }
+
     pub fn new(account_id: &str, api_key: &str) -> Self {
-        let headers = HeaderMap::from_iter([
-            (
-                "Authorization".parse().unwrap(),
-                format!("Bearer {}", api_key).parse().unwrap(),
-            ),
-            (
-                "Content-Type".parse().unwrap(),
-                "application/json".parse().unwrap(),
-            ),
-        ]);
+        Self::try_new(account_id, api_key).unwrap()
+    }
 
-        KvClient {
+    /// Like `new`, but returns an error instead of panicking if the HTTP client cannot be
+    /// initialized or `api_key` is not a valid header value.
+    pub fn try_new(account_id: &str, api_key: &str) -> Result<Self, KvError> {
+        Ok(KvClient {
             account_id: account_id.to_string(),
             api_key: api_key.to_string(),
-            client: Client::builder()
-                .connect_timeout(Duration::from_secs(5))
-                .build()
-                .unwrap(),
-            url: format!(
-                "{}{}{}{}",
-                CF_API_URL, "accounts/", account_id, "/storage/kv/namespaces"
-            ),
-            header_map: headers,
-        }
+            client: default_client_builder().build()?,
+            url: namespaces_url(account_id),
+            header_map: Arc::new(default_header_map(api_key)?),
+            retry_policy: Arc::new(ExponentialBackoff::default()),
+            bulk_concurrency: DEFAULT_CONCURRENCY,
+            max_pages: DEFAULT_MAX_PAGES,
+            observer: None,
+        })
     }
 
-    pub async fn list_namespaces(&self) -> Result<Vec<Namespace>, Box<dyn std::error::Error>> {
-        let resp = self
-            .client
-            .get(self.url.clone())
-            .headers(self.header_map.clone())
-            .send()
-            .await?;
+    /// Creates a client from the `CLOUDFLARE_ACCOUNT_ID` and `CLOUDFLARE_API_TOKEN`
+    /// environment variables.
+    pub fn from_env() -> Result<Self, KvError> {
+        Self::from_env_vars(ACCOUNT_ID_ENV, API_TOKEN_ENV)
+    }
 
-        if resp.status().is_success() == false {
-            warn!("Cloudflare returned an ERROR httpcode.")
-        }
+    /// Like `from_env`, but reads the account id and API token from the given variables.
+    pub fn from_env_vars(account_id_var: &str, api_token_var: &str) -> Result<Self, KvError> {
+        let account_id = std::env::var(account_id_var)
+            .map_err(|_| KvError::MissingEnvVar(account_id_var.to_string()))?;
+        let api_key = std::env::var(api_token_var)
+            .map_err(|_| KvError::MissingEnvVar(api_token_var.to_string()))?;
 
-        let resp_json = resp.json::<Value>().await?;
+        Self::try_new(&account_id, &api_key)
+    }
 
-        if check_success(resp_json.clone()).await? == false {
-            return Err(convert_string_to_error(resp_json.to_string().as_str()).await);
-        }
+    /// Creates a client authenticated with a legacy Global API Key instead of an API token.
+    pub fn with_global_key(account_id: &str, email: &str, key: &str) -> Self {
+        Self::try_with_global_key(account_id, email, key).unwrap()
+    }
 
-        match resp_json.get("result") {
-            Some(result) => match result.as_array() {
-                Some(namespaces) => {
-                    let mut namespace_list = Vec::new();
-                    for namespace in namespaces {
-                        let id = namespace["id"].as_str().unwrap().to_string();
-                        let title = namespace["title"].as_str().unwrap().to_string();
-                        namespace_list.push(Namespace { id, title });
-                    }
-                    Ok(namespace_list)
-                }
-                None => Err(convert_string_to_error(
-                    "The 'results' field cannot be converted to an array.",
-                )
-                .await),
-            },
-            None => Err(convert_string_to_error(
-                "The returned JSON does not contain the 'result' field.",
+    /// Like `with_global_key`, but returns an error instead of panicking if `email` or
+    /// `key` is not a valid header value.
+    pub fn try_with_global_key(account_id: &str, email: &str, key: &str) -> Result<Self, KvError> {
+        let header_map = global_key_header_map(email, key)?;
+        Self::try_with_header_map(account_id, key, Arc::new(header_map))
+    }
+
+    /// Creates a client that sends a pre-built, shared header map with every request.
+    ///
+    /// The map is used as-is, so it must contain the `Authorization: Bearer <api_key>`
+    /// and `Content-Type: application/json` headers expected by the Cloudflare API.
+    pub fn with_header_map(account_id: &str, api_key: &str, header_map: Arc<HeaderMap>) -> Self {
+        Self::try_with_header_map(account_id, api_key, header_map).unwrap()
+    }
+
+    /// Like `with_header_map`, but returns an error instead of panicking if the HTTP client
+    /// cannot be initialized.
+    pub fn try_with_header_map(
+        account_id: &str,
+        api_key: &str,
+        header_map: Arc<HeaderMap>,
+    ) -> Result<Self, KvError> {
+        Ok(KvClient {
+            account_id: account_id.to_string(),
+            api_key: api_key.to_string(),
+            client: default_client_builder().build()?,
+            url: namespaces_url(account_id),
+            header_map,
+            retry_policy: Arc::new(ExponentialBackoff::default()),
+            bulk_concurrency: DEFAULT_CONCURRENCY,
+            max_pages: DEFAULT_MAX_PAGES,
+            observer: None,
+        })
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(
+                method = "GET",
+                account_id = %self.account_id,
             )
-            .await),
-        }
+        )
+    )]
+    pub async fn list_namespaces(&self) -> Result<Vec<Namespace>, KvError> {
+        collect_namespace_pages(|page| self.fetch_namespaces_page(page)).await
     }
 
-    pub async fn create_namespace(
-        &self,
-        title: &str,
-    ) -> Result<Namespace, Box<dyn std::error::Error>> {
-        let payload = json!({
-            "title": title
-        });
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(
+                method = "GET",
+                account_id = %self.account_id,
+                page = page,
+                status,
+            )
+        )
+    )]
+    async fn fetch_namespaces_page(&self, page: usize) -> Result<Vec<Namespace>, KvError> {
+        let url = format!(
+            "{}?page={}&per_page={}",
+            self.url, page, NAMESPACES_PER_PAGE
+        );
+
         let resp = self
-            .client
-            .post(self.url.clone())
-            .headers(self.header_map.clone())
-            .json(&payload)
-            .send()
+            .send(
+                "list_namespaces",
+                None,
+                self.client.get(url).headers((*self.header_map).clone()),
+            )
             .await?;
 
-        if resp.status().is_success() == false {
-            warn!("Cloudflare returned an ERROR httpcode.")
-        }
+        let resp_json = handle_response(resp).await?;
+        let resp = parse_response::<Vec<Namespace>>(resp_json)?;
 
-        let resp_json = resp.json::<Value>().await?;
+        Ok(resp.result)
+    }
 
-        if check_success(resp_json.clone()).await? == false {
-            return Err(convert_string_to_error(resp_json.to_string().as_str()).await);
-        }
+    /// Checks credentials and connectivity with a single one-namespace listing request.
+    ///
+    /// A 401 or 403 answer is reported as `KvError::Unauthorized`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(
+                method = "GET",
+                account_id = %self.account_id,
+                status,
+            )
+        )
+    )]
+    pub async fn verify(&self) -> Result<(), KvError> {
+        let url = format!("{}?per_page=1", self.url);
 
-        match resp_json.get("result") {
-            Some(result) => {
-                let id = match result.get("id") {
-                    Some(id) => match id.as_str() {
-                        Some(id) => id,
-                        None => {
-                            return Err(convert_string_to_error(
-                                "The 'id' field cannot be converted to a string.",
-                            )
-                            .await)
-                        }
-                    },
-                    None => {
-                        return Err(convert_string_to_error(
-                            "The 'id' field cannot be found in the 'result' field.",
-                        )
-                        .await)
-                    }
-                };
-
-                let title = match result.get("title") {
-                    Some(title) => match title.as_str() {
-                        Some(title) => title,
-                        None => {
-                            return Err(convert_string_to_error(
-                                "The 'title' field cannot be converted to a string.",
-                            )
-                            .await)
-                        }
-                    },
-                    None => {
-                        return Err(convert_string_to_error(
-                            "The 'title' field cannot be found in the'result' field.",
-                        )
-                        .await)
-                    }
-                };
+        let resp = self
+            .send(
+                "verify",
+                None,
+                self.client.get(url).headers((*self.header_map).clone()),
+            )
+            .await?;
 
-                Ok(Namespace {
-                    id: id.to_string(),
-                    title: title.to_string(),
-                })
+        match handle_response(resp).await {
+            Ok(_) => Ok(()),
+            Err(KvError::Api { status, body }) if status == 401 || status == 403 => {
+                Err(KvError::Unauthorized { status, body })
             }
-            None => Err(convert_string_to_error(
-                "The returned JSON does not contain the 'result' field.",
-            )
-            .await),
+            Err(e) => Err(e),
         }
     }
-}
 
-#[derive(Clone, Debug)]
-pub struct KvNamespaceClient {
-    pub account_id: String,
-    pub api_key: String,
-    pub namespace_id: String,
-    client: Client,
-    url: String,
-    header_map: HeaderMap,
-}
+    /// Opens a pooled connection to the Cloudflare API ahead of time, so the TLS handshake
+    /// isn't paid by the first real request. Namespace clients created from this client
+    /// share the connection.
+    pub async fn warm_up(&self) -> Result<(), KvError> {
+        self.verify().await
+    }
 
-impl KvNamespaceClient {
-    pub fn new(account_id: &str, api_key: &str, namespace_id: &str) -> Self {
-        let headers = HeaderMap::from_iter([
-            (
-                "Authorization".parse().unwrap(),
-                format!("Bearer {}", api_key).parse().unwrap(),
-            ),
-            (
-                "Content-Type".parse().unwrap(),
-                "application/json".parse().unwrap(),
-            ),
-        ]);
+    pub async fn get_namespace_by_title(&self, title: &str) -> Result<Option<Namespace>, KvError> {
+        let namespaces = self.list_namespaces().await?;
+        Ok(namespaces
+            .into_iter()
+            .find(|namespace| namespace.title == title))
+    }
 
-        KvNamespaceClient {
-            account_id: account_id.to_string(),
-            api_key: api_key.to_string(),
-            namespace_id: namespace_id.to_string(),
-            client: Client::builder()
-                .connect_timeout(Duration::from_secs(5))
-                .build()
-                .unwrap(),
-            url: format!(
-                "{}{}{}{}{}",
-                CF_API_URL, "accounts/", account_id, "/storage/kv/namespaces/", namespace_id
-            ),
-            header_map: headers,
+    /// Lists the namespaces whose title contains `contains`, optionally ignoring case.
+    /// Cloudflare has no server-side title filter, so every page is still fetched.
+    pub async fn find_namespaces(
+        &self,
+        contains: &str,
+        case_insensitive: bool,
+    ) -> Result<Vec<Namespace>, KvError> {
+        let namespaces = self.list_namespaces().await?;
+
+        if case_insensitive {
+            let contains = contains.to_lowercase();
+            Ok(namespaces
+                .into_iter()
+                .filter(|namespace| namespace.title.to_lowercase().contains(&contains))
+                .collect())
+        } else {
+            Ok(namespaces
+                .into_iter()
+                .filter(|namespace| namespace.title.contains(contains))
+                .collect())
         }
     }
 
-    pub fn from_kvclient(kvclient: &KvClient, namespace_id: &str) -> Self {
-        KvNamespaceClient {
-            account_id: kvclient.account_id.clone(),
-            api_key: kvclient.api_key.clone(),
-            namespace_id: namespace_id.to_string(),
-            client: kvclient.client.clone(),
-            url: format!("{}/{}", kvclient.url.clone(), namespace_id),
-            header_map: kvclient.header_map.clone(),
+    pub async fn get_or_create_namespace(&self, title: &str) -> Result<Namespace, KvError> {
+        if let Some(namespace) = self.get_namespace_by_title(title).await? {
+            return Ok(namespace);
         }
-    }
 
-    pub async fn delete_namespace(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let resp = self
-            .client
-            .delete(self.url.clone())
-            .headers(self.header_map.clone())
-            .send()
-            .await?;
+        match self.create_namespace(title).await {
+            Ok(namespace) => Ok(namespace),
+            Err(e @ KvError::NamespaceExists { .. }) => {
+                match self.get_namespace_by_title(title).await? {
+                    Some(namespace) => Ok(namespace),
+                    None => Err(e),
+                }
+            }
+            Err(e) => Err(e),
+        }
+    }
 
-        if resp.status().is_success() == false {
-            warn!("Cloudflare returned an ERROR httpcode.")
+    /// Opens an existing namespace by title, failing with `NamespaceNotFound` if none matches.
+    pub async fn namespace_client_by_title(
+        &self,
+        title: &str,
+    ) -> Result<KvNamespaceClient, KvError> {
+        match self.get_namespace_by_title(title).await? {
+            Some(namespace) => KvNamespaceClient::from_kvclient(self, &namespace.id),
+            None => Err(KvError::NamespaceNotFound {
+                title: title.to_string(),
+            }),
         }
+    }
 
-        let resp_json = resp.json::<Value>().await?;
+    /// Deletes the namespace titled `title`, failing with `NamespaceNotFound` if none matches.
+    pub async fn delete_namespace_by_title(&self, title: &str) -> Result<(), KvError> {
+        self.namespace_client_by_title(title)
+            .await?
+            .delete_namespace()
+            .await
+    }
 
-        if check_success(resp_json.clone()).await? == false {
-            return Err(convert_string_to_error(resp_json.to_string().as_str()).await);
-        }
-        Ok(())
+    pub async fn create_namespace_client(&self, title: &str) -> Result<KvNamespaceClient, KvError> {
+        let namespace = self.create_namespace(title).await?;
+        KvNamespaceClient::from_kvclient(self, &namespace.id)
     }
 
-    pub async fn rename_namespace(
-        &self,
-        new_title: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    /// Creates a namespace, failing with `KvError::NamespaceExists` if the title is taken.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(
+                method = "POST",
+                account_id = %self.account_id,
+                title = %title,
+                status,
+            )
+        )
+    )]
+    pub async fn create_namespace(&self, title: &str) -> Result<Namespace, KvError> {
         let payload = json!({
-            "title": new_title
+            "title": title
         });
-
+        debug_body("POST", &self.url, &payload);
         let resp = self
-            .client
-            .put(self.url.clone())
-            .headers(self.header_map.clone())
-            .json(&payload)
-            .send()
+            .send(
+                "create_namespace",
+                None,
+                self.client
+                    .post(self.url.clone())
+                    .headers((*self.header_map).clone())
+                    .json(&payload),
+            )
             .await?;
 
-        if resp.status().is_success() == false {
-            warn!("Cloudflare returned an ERROR httpcode.")
-        }
+        let resp_json = match handle_response(resp).await {
+            Ok(resp_json) => resp_json,
+            Err(e) if e.has_error_code(NAMESPACE_EXISTS_CODE) => {
+                return Err(KvError::NamespaceExists {
+                    title: title.to_string(),
+                });
+            }
+            Err(e) => return Err(e.denied_as("create a namespace")),
+        };
+        let resp = parse_response::<Namespace>(resp_json)?;
 
-        let resp_json = resp.json::<Value>().await?;
+        Ok(resp.result)
+    }
+}
+
+#[derive(Clone)]
+pub struct KvClientBuilder {
+    account_id: String,
+    api_key: String,
+    http2_prior_knowledge: bool,
+    http2_adaptive_window: bool,
+    retry_policy: Arc<dyn RetryPolicy>,
+    bulk_concurrency: usize,
+    max_pages: usize,
+    observer: Option<Arc<dyn KvObserver>>,
+    headers: Vec<(String, String)>,
+    user_agent: String,
+    #[cfg(not(target_arch = "wasm32"))]
+    proxy: Option<reqwest::Proxy>,
+    #[cfg(not(target_arch = "wasm32"))]
+    connect_timeout: Option<Duration>,
+    #[cfg(not(target_arch = "wasm32"))]
+    pool_max_idle_per_host: Option<usize>,
+    #[cfg(not(target_arch = "wasm32"))]
+    pool_idle_timeout: Option<Duration>,
+    #[cfg(not(target_arch = "wasm32"))]
+    tcp_keepalive: Option<Duration>,
+}
 
-        if check_success(resp_json.clone()).await? == false {
-            return Err(convert_string_to_error(resp_json.to_string().as_str()).await);
+impl KvClientBuilder {
+    pub fn new(account_id: &str, api_key: &str) -> Self {
+        KvClientBuilder {
+            account_id: account_id.to_string(),
+            api_key: api_key.to_string(),
+            http2_prior_knowledge: false,
+            http2_adaptive_window: false,
+            retry_policy: Arc::new(ExponentialBackoff::default()),
+            bulk_concurrency: DEFAULT_CONCURRENCY,
+            max_pages: DEFAULT_MAX_PAGES,
+            observer: None,
+            headers: Vec::new(),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            #[cfg(not(target_arch = "wasm32"))]
+            proxy: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            connect_timeout: Some(DEFAULT_CONNECT_TIMEOUT),
+            #[cfg(not(target_arch = "wasm32"))]
+            pool_max_idle_per_host: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            pool_idle_timeout: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            tcp_keepalive: None,
         }
+    }
 
-        Ok(())
+    /// Talks HTTP/2 to the Cloudflare API without negotiating it first, so concurrent
+    /// requests are multiplexed over a single connection.
+    pub fn http2_prior_knowledge(mut self) -> Self {
+        self.http2_prior_knowledge = true;
+        self
     }
-    pub async fn write(&self, payload: KvRequest) -> Result<(), Box<dyn std::error::Error>> {
-        let url = format!("{}/bulk", self.url);
+
+    pub fn http2_adaptive_window(mut self, enabled: bool) -> Self {
+        self.http2_adaptive_window = enabled;
+        self
+    }
+
+    /// Sets how many times a failed bulk write chunk is retried before giving up, using
+    /// the default `ExponentialBackoff` timings. Replaces any policy set before.
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.retry_policy = Arc::new(ExponentialBackoff {
+            max_retries,
+            ..ExponentialBackoff::default()
+        });
+        self
+    }
+
+    /// Sets the policy deciding whether and when failed bulk write chunks are retried.
+    /// Defaults to `ExponentialBackoff::default()`.
+    pub fn retry_policy(mut self, retry_policy: impl RetryPolicy + 'static) -> Self {
+        self.retry_policy = Arc::new(retry_policy);
+        self
+    }
+
+    /// Installs an observer notified around every HTTP request. Namespace clients created
+    /// from the built client share it. Not available on wasm32, which has no monotonic
+    /// clock to time requests with.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn observer(mut self, observer: impl KvObserver + 'static) -> Self {
+        self.observer = Some(Arc::new(observer));
+        self
+    }
+
+    /// Sets how many bulk write chunks `write_multiple` sends at once. Defaults to 4.
+    pub fn bulk_concurrency(mut self, bulk_concurrency: usize) -> Self {
+        self.bulk_concurrency = bulk_concurrency.max(1);
+        self
+    }
+
+    /// Sets how many pages a key listing may fetch before failing with
+    /// `KvError::Pagination`. Defaults to 100,000.
+    pub fn max_pages(mut self, max_pages: usize) -> Self {
+        self.max_pages = max_pages.max(1);
+        self
+    }
+
+    /// Replaces the default `cloudflare-workers-kv-sdk-rs/<version>` User-Agent.
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = user_agent.to_string();
+        self
+    }
+
+    /// Adds a header sent with every request, e.g. `CF-Access-Client-Id` for accounts
+    /// behind Cloudflare Access. Namespace clients created from the built client inherit it.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Routes all requests through `proxy`.
+    ///
+    /// Without an explicit proxy, the `HTTPS_PROXY`/`ALL_PROXY` environment variables are
+    /// still honoured.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Overrides the default 5 second connect timeout. `None` waits for connections
+    /// without a limit, for high-latency links.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn connect_timeout(mut self, timeout: impl Into<Option<Duration>>) -> Self {
+        self.connect_timeout = timeout.into();
+        self
+    }
+
+    /// Caps the idle connections kept open to the Cloudflare API. reqwest keeps an
+    /// unlimited number by default.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// Sets how long an idle pooled connection is kept before being closed. reqwest's
+    /// default is 90 seconds.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Enables TCP keepalive probes at the given interval. Off by default.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn tcp_keepalive(mut self, interval: Duration) -> Self {
+        self.tcp_keepalive = Some(interval);
+        self
+    }
+
+    pub fn build(self) -> Result<KvClient, KvError> {
+        let mut header_map = default_header_map(&self.api_key)?;
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| KvError::InvalidHeader(format!("{}: {}", name, e)))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|e| KvError::InvalidHeader(format!("{}: {}", name, e)))?;
+            header_map.insert(name, value);
+        }
+
+        // Starts from a bare builder rather than `default_client_builder`, since reqwest
+        // can't unset a connect timeout once it is set.
+        let client_builder = Client::builder().user_agent(self.user_agent);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let client_builder = {
+            let mut client_builder =
+                client_builder.http2_adaptive_window(self.http2_adaptive_window);
+            if self.http2_prior_knowledge {
+                client_builder = client_builder.http2_prior_knowledge();
+            }
+            if let Some(proxy) = self.proxy {
+                client_builder = client_builder.proxy(proxy);
+            }
+            if let Some(timeout) = self.connect_timeout {
+                client_builder = client_builder.connect_timeout(timeout);
+            }
+            if let Some(max) = self.pool_max_idle_per_host {
+                client_builder = client_builder.pool_max_idle_per_host(max);
+            }
+            if let Some(timeout) = self.pool_idle_timeout {
+                client_builder = client_builder.pool_idle_timeout(timeout);
+            }
+            if let Some(interval) = self.tcp_keepalive {
+                client_builder = client_builder.tcp_keepalive(interval);
+            }
+            client_builder
+        };
+
+        Ok(KvClient {
+            account_id: self.account_id.clone(),
+            api_key: self.api_key.clone(),
+            client: client_builder.build()?,
+            url: namespaces_url(&self.account_id),
+            header_map: Arc::new(header_map),
+            retry_policy: self.retry_policy,
+            bulk_concurrency: self.bulk_concurrency,
+            max_pages: self.max_pages,
+            observer: self.observer,
+        })
+    }
+}
+
+// Cursor state of a key listing that refuses to follow a cursor that doesn't move
+// or to fetch more than `max_pages` pages, so a misbehaving API can't loop forever.
+struct KeyPager {
+    cursor: String,
+    pages: usize,
+    max_pages: usize,
+}
+
+impl KeyPager {
+    fn cursor(&self) -> &str {
+        &self.cursor
+    }
+
+    // Moves to the cursor of the next page, returning `false` once there is none.
+    fn advance(&mut self, next: String) -> Result<bool, KvError> {
+        if next.is_empty() {
+            return Ok(false);
+        }
+        if next == self.cursor {
+            return Err(KvError::Pagination(format!(
+                "Cloudflare returned the same cursor '{}' twice",
+                next
+            )));
+        }
+        if self.pages >= self.max_pages {
+            return Err(KvError::Pagination(format!(
+                "exceeded the limit of {} pages",
+                self.max_pages
+            )));
+        }
+
+        self.pages += 1;
+        self.cursor = next;
+        Ok(true)
+    }
+}
+
+// Fetches key pages from the pager's cursor until the listing is complete, returning
+// every key name and the sum of the per-page counts.
+async fn collect_key_pages<F, Fut>(
+    mut pager: KeyPager,
+    mut fetch_page: F,
+) -> Result<(Vec<String>, u64), KvError>
+where
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = Result<(Vec<KeyInfo>, ResultInfo), KvError>>,
+{
+    let mut keys = Vec::new();
+    let mut count = 0;
+    loop {
+        let (page_keys, result_info) = fetch_page(pager.cursor().to_string()).await?;
+        keys.extend(page_keys.into_iter().map(|key| key.name));
+        count += result_info.count;
+
+        if !pager.advance(result_info.next_cursor())? {
+            break;
+        }
+    }
+    Ok((keys, count))
+}
+
+/// Decides whether and when a failed bulk write chunk is retried.
+///
+/// On wasm32, which has no timer to wait with, failed chunks are never retried and the
+/// policy is not consulted.
+pub trait RetryPolicy: std::fmt::Debug + Send + Sync {
+    /// Returns how long to wait before retrying after the `attempt`-th failure (counting
+    /// from 0) with `error`, or `None` to give up and return the error.
+    fn backoff(&self, attempt: u32, error: &KvError) -> Option<Duration>;
+}
+
+/// Retries errors for which `KvError::is_retryable` holds with exponentially growing waits.
+///
+/// The wait doubles from `initial_backoff` on every attempt, capped at `max_backoff`.
+/// With `jitter`, each wait is instead drawn uniformly between zero and that value, which
+/// spreads out retries from many clients failing at once.
+#[derive(Clone, Debug)]
+pub struct ExponentialBackoff {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub jitter: bool,
+}
+
+impl Default for ExponentialBackoff {
+    fn default() -> Self {
+        ExponentialBackoff {
+            max_retries: DEFAULT_MAX_RETRIES,
+            initial_backoff: RETRY_BACKOFF,
+            max_backoff: MAX_RETRY_BACKOFF,
+            jitter: false,
+        }
+    }
+}
+
+impl RetryPolicy for ExponentialBackoff {
+    fn backoff(&self, attempt: u32, error: &KvError) -> Option<Duration> {
+        if attempt >= self.max_retries || !error.is_retryable() {
+            return None;
+        }
+
+        let delay = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff);
+
+        if self.jitter {
+            // `RandomState` is seeded randomly per instance, which is enough entropy for
+            // jitter without pulling in a random number crate.
+            let random = RandomState::new().build_hasher().finish();
+            Some(delay.mul_f64(random as f64 / u64::MAX as f64))
+        } else {
+            Some(delay)
+        }
+    }
+}
+
+/// Receives a callback around every HTTP request a client sends, for recording metrics
+/// without this crate depending on a metrics library.
+///
+/// `op` names the client operation, such as `"get"` or `"write_multiple"`, and `key` is
+/// the key it targets, if it targets a single one. Both methods do nothing by default.
+pub trait KvObserver: std::fmt::Debug + Send + Sync {
+    fn on_request(&self, op: &str, key: Option<&str>) {
+        let _ = (op, key);
+    }
+
+    /// Called once the response headers arrive, with `None` as the status if the request
+    /// failed without a response.
+    fn on_response(&self, op: &str, status: Option<u16>, duration: Duration) {
+        let _ = (op, status, duration);
+    }
+}
+
+async fn send_observed(
+    observer: Option<&dyn KvObserver>,
+    op: &str,
+    key: Option<&str>,
+    request: reqwest::RequestBuilder,
+) -> Result<reqwest::Response, KvError> {
+    let Some(observer) = observer else {
+        return Ok(request.send().await?);
+    };
+
+    observer.on_request(op, key);
+    let started = Instant::now();
+    let result = request.send().await;
+    let status = result.as_ref().ok().map(|resp| resp.status().as_u16());
+    observer.on_response(op, status, started.elapsed());
+
+    Ok(result?)
+}
+
+#[derive(Clone)]
+struct BeforeWriteHook(Arc<dyn Fn(usize) -> Result<(), String> + Send + Sync>);
+
+impl std::fmt::Debug for BeforeWriteHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("BeforeWriteHook")
+    }
+}
+
+#[derive(Clone)]
+pub struct KvNamespaceClient {
+    pub account_id: String,
+    pub api_key: String,
+    pub namespace_id: String,
+    client: Client,
+    url: String,
+    header_map: Arc<HeaderMap>,
+    before_write: Option<BeforeWriteHook>,
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    retry_policy: Arc<dyn RetryPolicy>,
+    bulk_concurrency: usize,
+    max_pages: usize,
+    observer: Option<Arc<dyn KvObserver>>,
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    keys_cache: KeysCache,
+}
+
+// Shared between clones, since they all point at the same namespace.
+type KeysCache = Arc<Mutex<Option<(Instant, Vec<String>)>>>;
+
+impl FromStr for KvNamespaceClient {
+    type Err = KvError;
+
+    /// Parses a `cfkv://` connection string, see `KvNamespaceClient::from_url`.
+    fn from_str(url: &str) -> Result<Self, Self::Err> {
+        Self::from_url(url)
+    }
+}
+
+impl std::fmt::Debug for KvNamespaceClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KvNamespaceClient")
+            .field("account_id", &self.account_id)
+            .field("api_key", &"[REDACTED]")
+            .field("namespace_id", &self.namespace_id)
+            .field("url", &self.url)
+            .field("before_write", &self.before_write)
+            .finish()
+    }
+}
+
+impl KvNamespaceClient {
+    async fn send(
+        &self,
+        op: &str,
+        key: Option<&str>,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, KvError> {
+        send_observed(self.observer.as_deref(), op, key, request).await
+    }
+
+    pub fn new(account_id: &str, api_key: &str, namespace_id: &str) -> Self {
+        Self::try_new(account_id, api_key, namespace_id).unwrap()
+    }
+
+    /// Like `new`, but returns an error instead of panicking if the HTTP client cannot be
+    /// initialized, `api_key` is not a valid header value or `namespace_id` is not a
+    /// 32-character hex id.
+    pub fn try_new(account_id: &str, api_key: &str, namespace_id: &str) -> Result<Self, KvError> {
+        validate_namespace_id(namespace_id)?;
+
+        Ok(KvNamespaceClient {
+            account_id: account_id.to_string(),
+            api_key: api_key.to_string(),
+            namespace_id: namespace_id.to_string(),
+            client: default_client_builder().build()?,
+            url: join_url(&namespaces_url(account_id), namespace_id),
+            header_map: Arc::new(default_header_map(api_key)?),
+            before_write: None,
+            retry_policy: Arc::new(ExponentialBackoff::default()),
+            bulk_concurrency: DEFAULT_CONCURRENCY,
+            max_pages: DEFAULT_MAX_PAGES,
+            observer: None,
+            keys_cache: KeysCache::default(),
+        })
+    }
+
+    /// Creates a client for `namespace_id` sharing the connection pool and headers of
+    /// `kvclient`. The id must be the 32-character hex id Cloudflare assigns to namespaces.
+    pub fn from_kvclient(kvclient: &KvClient, namespace_id: &str) -> Result<Self, KvError> {
+        validate_namespace_id(namespace_id)?;
+
+        Ok(KvNamespaceClient {
+            account_id: kvclient.account_id.clone(),
+            api_key: kvclient.api_key.clone(),
+            namespace_id: namespace_id.to_string(),
+            client: kvclient.client.clone(),
+            url: join_url(&kvclient.url, namespace_id),
+            header_map: kvclient.header_map.clone(),
+            before_write: None,
+            retry_policy: kvclient.retry_policy.clone(),
+            bulk_concurrency: kvclient.bulk_concurrency,
+            max_pages: kvclient.max_pages,
+            observer: kvclient.observer.clone(),
+            keys_cache: KeysCache::default(),
+        })
+    }
+
+    /// Creates a client from a connection string of the form
+    /// `cfkv://<account_id>:<api_token>@/<namespace_id>`, so a single environment variable
+    /// can carry the whole configuration. The account id and token may be percent-encoded.
+    pub fn from_url(url: &str) -> Result<Self, KvError> {
+        // Errors never echo `url`, since it contains the token.
+        let rest = url
+            .strip_prefix("cfkv://")
+            .ok_or_else(|| KvError::InvalidUrl("expected the cfkv:// scheme".to_string()))?;
+        let (credentials, namespace_id) = rest.split_once("@/").ok_or_else(|| {
+            KvError::InvalidUrl("expected '@/' before the namespace id".to_string())
+        })?;
+        let (account_id, api_key) = credentials.split_once(':').ok_or_else(|| {
+            KvError::InvalidUrl("expected '<account_id>:<api_token>' credentials".to_string())
+        })?;
+
+        let decode = |part: &str, name: &str| {
+            let decoded = percent_decode_str(part)
+                .decode_utf8()
+                .map_err(|_| KvError::InvalidUrl(format!("{} is not valid UTF-8", name)))?;
+            if decoded.is_empty() {
+                return Err(KvError::InvalidUrl(format!("{} is empty", name)));
+            }
+            Ok(decoded.into_owned())
+        };
+        let account_id = decode(account_id, "account id")?;
+        let api_key = decode(api_key, "API token")?;
+
+        Self::try_new(&account_id, &api_key, namespace_id)
+    }
+
+    /// Installs a hook that is called with the size in bytes of every write's request
+    /// body before it is sent. Returning `Err` aborts the write with
+    /// `KvError::WriteRejected`.
+    ///
+    /// The size is what goes over the wire, so it depends on the method: `write` and
+    /// `write_multiple` report the serialized JSON body, in which binary values are
+    /// base64-encoded and about a third larger, while `put` and `write_streaming` report
+    /// the raw value alone, without `put`'s metadata. Streamed bodies of unknown length
+    /// skip the hook.
+    ///
+    /// Cloudflare's namespace and account storage limits can't be queried through this
+    /// SDK, so the hook is the place to enforce a self-imposed budget instead.
+    pub fn on_before_write<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(usize) -> Result<(), String> + Send + Sync + 'static,
+    {
+        self.before_write = Some(BeforeWriteHook(Arc::new(hook)));
+        self
+    }
+
+    /// Sets how many bulk write chunks `write_multiple` sends at once. Defaults to 4.
+    pub fn with_bulk_concurrency(mut self, bulk_concurrency: usize) -> Self {
+        self.bulk_concurrency = bulk_concurrency.max(1);
+        self
+    }
+
+    /// Sets the policy deciding whether and when failed bulk write chunks are retried.
+    pub fn with_retry_policy(mut self, retry_policy: impl RetryPolicy + 'static) -> Self {
+        self.retry_policy = Arc::new(retry_policy);
+        self
+    }
+
+    /// Installs an observer notified around every HTTP request. Not available on wasm32,
+    /// which has no monotonic clock to time requests with.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_observer(mut self, observer: impl KvObserver + 'static) -> Self {
+        self.observer = Some(Arc::new(observer));
+        self
+    }
+
+    /// Sets how many pages a key listing may fetch before failing with
+    /// `KvError::Pagination`. Defaults to 100,000.
+    pub fn with_max_pages(mut self, max_pages: usize) -> Self {
+        self.max_pages = max_pages.max(1);
+        self
+    }
+
+    fn key_pager(&self) -> KeyPager {
+        KeyPager {
+            cursor: String::new(),
+            pages: 1,
+            max_pages: self.max_pages,
+        }
+    }
+
+    // reqwest appends rather than replaces headers set on the request builder, so
+    // requests with a non-JSON body must start from a map without `Content-Type`.
+    fn header_map_without_content_type(&self) -> HeaderMap {
+        let mut header_map = (*self.header_map).clone();
+        header_map.remove(CONTENT_TYPE);
+        header_map
+    }
+
+    fn check_before_write(&self, body: &[u8]) -> Result<(), KvError> {
+        match &self.before_write {
+            Some(hook) => (hook.0)(body.len()).map_err(KvError::WriteRejected),
+            None => Ok(()),
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(
+                method = "DELETE",
+                namespace_id = %self.namespace_id,
+                status,
+            )
+        )
+    )]
+    pub async fn delete_namespace(&self) -> Result<(), KvError> {
+        let resp = self
+            .send(
+                "delete_namespace",
+                None,
+                self.client
+                    .delete(self.url.clone())
+                    .headers((*self.header_map).clone()),
+            )
+            .await?;
+
+        handle_response(resp)
+            .await
+            .map_err(|e| e.denied_as("delete the namespace"))?;
+        Ok(())
+    }
+
+    /// Fetches this namespace's current id and title.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(
+                method = "GET",
+                namespace_id = %self.namespace_id,
+                status,
+            )
+        )
+    )]
+    pub async fn get_namespace_info(&self) -> Result<Namespace, KvError> {
+        let resp = self
+            .send(
+                "get_namespace_info",
+                None,
+                self.client
+                    .get(self.url.clone())
+                    .headers((*self.header_map).clone()),
+            )
+            .await?;
+
+        let resp_json = handle_response(resp).await?;
+        let resp = parse_response::<Namespace>(resp_json)?;
+
+        Ok(resp.result)
+    }
+
+    /// Returns the key count and stored bytes of this namespace from Cloudflare's GraphQL
+    /// analytics API.
+    ///
+    /// The figures are the most recent daily snapshot from the past week, so they lag
+    /// behind recent writes; a namespace without a snapshot yet reports zero. Not
+    /// available on wasm32, which has no system clock to pick the window with.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(
+                method = "POST",
+                path = "graphql",
+                namespace_id = %self.namespace_id,
+                status,
+            )
+        )
+    )]
+    pub async fn storage_usage(&self) -> Result<StorageStats, KvError> {
+        let since = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .saturating_sub(STORAGE_USAGE_WINDOW);
+        let url = join_url(CF_API_URL, "graphql");
+        let payload = json!({
+            "query": STORAGE_USAGE_QUERY,
+            "variables": {
+                "accountTag": self.account_id,
+                "namespaceId": self.namespace_id,
+                "since": utc_date(since.as_secs()),
+            }
+        });
+        debug_body("POST", &url, &payload);
+
+        let resp = self
+            .send(
+                "storage_usage",
+                None,
+                self.client
+                    .post(url)
+                    .headers((*self.header_map).clone())
+                    .json(&payload),
+            )
+            .await?;
+
+        if !resp.status().is_success() {
+            return Err(error_from_response(resp).await);
+        }
+        let status = resp.status().as_u16();
+        record_status(status);
+
+        // GraphQL has no `success` field and reports query errors with a 200 status.
+        let resp_json = resp.json::<Value>().await?;
+        if resp_json
+            .get("errors")
+            .and_then(|errors| errors.as_array())
+            .is_some_and(|errors| !errors.is_empty())
+        {
+            return Err(KvError::Api {
+                status,
+                body: resp_json.to_string(),
+            });
+        }
+
+        let groups = resp_json
+            .pointer("/data/viewer/accounts/0/kvStorageAdaptiveGroups")
+            .and_then(|groups| groups.as_array())
+            .ok_or_else(|| {
+                KvError::MalformedResponse("missing kvStorageAdaptiveGroups".to_string())
+            })?;
+
+        match groups.first().and_then(|group| group.get("max")) {
+            Some(max) => serde_json::from_value(max.clone())
+                .map_err(|e| KvError::MalformedResponse(e.to_string())),
+            None => Ok(StorageStats::default()),
+        }
+    }
+
+    /// Renames the namespace and returns it with its new title.
+    ///
+    /// If Cloudflare doesn't echo the namespace back, it is built from the namespace id
+    /// and `new_title`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(
+                method = "PUT",
+                namespace_id = %self.namespace_id,
+                new_title = %new_title,
+                status,
+            )
+        )
+    )]
+    pub async fn rename_namespace(&self, new_title: &str) -> Result<Namespace, KvError> {
+        let payload = json!({
+            "title": new_title
+        });
+        debug_body("PUT", &self.url, &payload);
+
+        let resp = self
+            .send(
+                "rename_namespace",
+                None,
+                self.client
+                    .put(self.url.clone())
+                    .headers((*self.header_map).clone())
+                    .json(&payload),
+            )
+            .await?;
+
+        let resp_json = handle_response(resp)
+            .await
+            .map_err(|e| e.denied_as("rename the namespace"))?;
+
+        let result = resp_json.get("result");
+        let id = result
+            .and_then(|result| result.get("id"))
+            .and_then(|id| id.as_str())
+            .unwrap_or(&self.namespace_id);
+        let title = result
+            .and_then(|result| result.get("title"))
+            .and_then(|title| title.as_str())
+            .unwrap_or(new_title);
+
+        Ok(Namespace {
+            id: id.to_string(),
+            title: title.to_string(),
+        })
+    }
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(
+                method = "PUT",
+                path = "bulk",
+                namespace_id = %self.namespace_id,
+                key = %payload.key,
+                status,
+            )
+        )
+    )]
+    pub async fn write(&self, payload: KvRequest) -> Result<(), KvError> {
+        let url = format!("{}/bulk", self.url);
+
+        payload.validate()?;
 
         let payload_vec = vec![payload];
+        let body = serde_json::to_vec(&payload_vec).map_err(KvError::Serialize)?;
+        self.check_before_write(&body)?;
+        debug_bytes("PUT", &url, &body);
+
+        let resp = self
+            .send(
+                "write",
+                Some(&payload_vec[0].key),
+                self.client
+                    .put(url)
+                    .headers((*self.header_map).clone())
+                    .body(body),
+            )
+            .await?;
+
+        handle_response(resp)
+            .await
+            .map_err(|e| e.denied_as("write"))?;
+
+        Ok(())
+    }
+
+    /// Uploads `body` as the value of `key` through the single-key endpoint instead of the
+    /// bulk JSON one, so the value never has to be held in memory as a whole. With the
+    /// `stream` feature, `reqwest::Body::wrap_stream` turns a file or stream into a body.
+    ///
+    /// The `on_before_write` hook only sees bodies whose size is known up front.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(
+                method = "PUT",
+                path = "values",
+                namespace_id = %self.namespace_id,
+                key = %key.as_ref(),
+                status,
+            )
+        )
+    )]
+    pub async fn write_streaming(
+        &self,
+        key: impl AsRef<str>,
+        body: impl Into<reqwest::Body>,
+    ) -> Result<(), KvError> {
+        let url = format!("{}/values/{}", self.url, encode_key(key.as_ref()));
+        let body = body.into();
+
+        if let Some(bytes) = body.as_bytes() {
+            self.check_before_write(bytes)?;
+        }
 
         let resp = self
+            .send(
+                "write_streaming",
+                Some(key.as_ref()),
+                self.client
+                    .put(url)
+                    .headers(self.header_map_without_content_type())
+                    .header(CONTENT_TYPE, "application/octet-stream")
+                    .body(body),
+            )
+            .await?;
+
+        handle_response(resp)
+            .await
+            .map_err(|e| e.denied_as("write"))?;
+
+        Ok(())
+    }
+
+    /// Writes a single value through `PUT /values/{key}` rather than the bulk endpoint, so
+    /// the value is sent as-is instead of inside JSON. Metadata, if any, goes in a
+    /// multipart field next to the value.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(
+                method = "PUT",
+                path = "values",
+                namespace_id = %self.namespace_id,
+                key = %key.as_ref(),
+                status,
+            )
+        )
+    )]
+    pub async fn put(
+        &self,
+        key: impl AsRef<str>,
+        value: impl Into<Vec<u8>>,
+        opts: WriteOptions,
+    ) -> Result<(), KvError> {
+        let key = key.as_ref();
+        let value = value.into();
+
+        validate_expiration(key, opts.expiration, opts.expiration_ttl)?;
+        self.check_before_write(&value)?;
+
+        let mut query = Vec::new();
+        if let Some(expiration) = opts.expiration {
+            query.push(("expiration", expiration));
+        }
+        if let Some(expiration_ttl) = opts.expiration_ttl {
+            query.push(("expiration_ttl", expiration_ttl));
+        }
+
+        let url = format!("{}/values/{}", self.url, encode_key(key));
+        let request = self
             .client
             .put(url)
-            .headers(self.header_map.clone())
-            .json(&payload_vec)
-            .send()
+            .query(&query)
+            .headers(self.header_map_without_content_type());
+
+        let request = match opts.metadata {
+            Some(metadata) => {
+                let metadata = serde_json::to_string(&metadata).map_err(KvError::Serialize)?;
+                let form = reqwest::multipart::Form::new()
+                    .part("value", reqwest::multipart::Part::bytes(value))
+                    .text("metadata", metadata);
+                request.multipart(form)
+            }
+            None => request
+                .header(CONTENT_TYPE, "application/octet-stream")
+                .body(value),
+        };
+
+        let resp = self.send("put", Some(key), request).await?;
+
+        handle_response(resp)
+            .await
+            .map_err(|e| e.denied_as("write"))?;
+
+        Ok(())
+    }
+
+    /// Writes `value` with `content_type` recorded under the `content_type` field of its
+    /// metadata, merged into any metadata object in `opts`.
+    ///
+    /// KV has no native content type; this is a metadata convention read back by
+    /// `get_content_type`, and it uses up part of the 1024-byte metadata limit.
+    pub async fn put_with_content_type(
+        &self,
+        key: impl AsRef<str>,
+        value: impl Into<Vec<u8>>,
+        content_type: &str,
+        mut opts: WriteOptions,
+    ) -> Result<(), KvError> {
+        let mut metadata = match opts.metadata.take() {
+            Some(Value::Object(metadata)) => metadata,
+            None => serde_json::Map::new(),
+            Some(metadata) => {
+                return Err(KvError::UnexpectedType(format!(
+                    "The metadata must be a JSON object to hold a content type: {}",
+                    metadata
+                )));
+            }
+        };
+        metadata.insert(CONTENT_TYPE_FIELD.to_string(), json!(content_type));
+        opts.metadata = Some(Value::Object(metadata));
+
+        self.put(key, value, opts).await
+    }
+
+    /// Writes `value` under `key` to expire `ttl_sec` seconds from now, which must be at
+    /// least 60.
+    pub async fn write_with_ttl(
+        &self,
+        key: impl AsRef<str>,
+        value: &str,
+        ttl_sec: u64,
+    ) -> Result<(), KvError> {
+        self.write(KvRequest::new(key.as_ref(), value).ttl_sec(ttl_sec))
+            .await
+    }
+
+    /// Writes `payload` only if its key doesn't exist yet, returning whether it was written.
+    ///
+    /// KV has no compare-and-set, so the existence check and the write are two requests:
+    /// a concurrent writer can still create the key in between and be overwritten.
+    pub async fn write_if_absent(&self, payload: KvRequest) -> Result<bool, KvError> {
+        if self.exists(&payload.key).await? {
+            return Ok(false);
+        }
+
+        self.write(payload).await?;
+        Ok(true)
+    }
+
+    /// Writes `payload` in chunks of 10,000 requests, sending up to `bulk_concurrency`
+    /// chunks at once and retrying each failed chunk on its own.
+    ///
+    /// Every request is validated before anything is sent; if any is invalid, nothing is
+    /// written and all of them are reported by index in `KvError::BatchValidation`.
+    ///
+    /// Chunks that succeed are kept even if others fail; chunks that still fail after
+    /// exhausting the retries are reported by index in `KvError::ChunksFailed`.
+    pub async fn write_multiple(&self, payload: Vec<KvRequest>) -> Result<(), KvError> {
+        let invalid = payload
+            .iter()
+            .enumerate()
+            .filter_map(|(index, request)| request.validate().err().map(|e| (index, e)))
+            .collect::<Vec<_>>();
+        if !invalid.is_empty() {
+            return Err(KvError::BatchValidation(invalid));
+        }
+
+        let mut failures = stream::iter(payload.chunks(BULK_LIMIT).enumerate().map(
+            |(index, chunk)| async move {
+                self.write_chunk_with_retry(chunk)
+                    .await
+                    .err()
+                    .map(|e| (index, e))
+            },
+        ))
+        .buffer_unordered(self.bulk_concurrency)
+        .filter_map(|failure| async move { failure })
+        .collect::<Vec<_>>()
+        .await;
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            failures.sort_by_key(|(index, _)| *index);
+            Err(KvError::ChunksFailed(failures))
+        }
+    }
+
+    /// Like `write_multiple`, but when several requests share a key only the last one is
+    /// written, so the outcome doesn't depend on how Cloudflare orders a batch. Requests
+    /// keep the relative order of their last occurrences.
+    pub async fn write_multiple_dedup(&self, payload: Vec<KvRequest>) -> Result<(), KvError> {
+        let mut seen = HashSet::new();
+        let mut payload = payload
+            .into_iter()
+            .rev()
+            .filter(|request| seen.insert(request.key.clone()))
+            .collect::<Vec<_>>();
+        payload.reverse();
+
+        self.write_multiple(payload).await
+    }
+
+    /// Writes plain string key/value pairs with no expiration or metadata through
+    /// `write_multiple`.
+    pub async fn write_pairs(
+        &self,
+        pairs: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
+    ) -> Result<(), KvError> {
+        let payload = pairs
+            .into_iter()
+            .map(|(key, value)| KvRequest {
+                key: key.into(),
+                value: value.into(),
+                base64: false,
+                expiration: None,
+                expiration_ttl: None,
+                metadata: None,
+            })
+            .collect();
+
+        self.write_multiple(payload).await
+    }
+
+    async fn write_chunk_with_retry(&self, chunk: &[KvRequest]) -> Result<(), KvError> {
+        // wasm32 has no timer to wait between attempts with, and retrying back-to-back
+        // would only hammer the API, so chunks are sent once there.
+        #[cfg(target_arch = "wasm32")]
+        return self.write_chunk(chunk).await;
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let mut attempt = 0;
+            loop {
+                match self.write_chunk(chunk).await {
+                    Ok(()) => return Ok(()),
+                    Err(e) => match self.retry_policy.backoff(attempt, &e) {
+                        Some(delay) => {
+                            warn!("Bulk write chunk failed, retrying in {:?}: {}", delay, e);
+                            tokio::time::sleep(delay).await;
+                            attempt += 1;
+                        }
+                        None => return Err(e),
+                    },
+                }
+            }
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(
+                method = "PUT",
+                path = "bulk",
+                namespace_id = %self.namespace_id,
+                len = chunk.len(),
+                status,
+            )
+        )
+    )]
+    async fn write_chunk(&self, chunk: &[KvRequest]) -> Result<(), KvError> {
+        let url = format!("{}/bulk", self.url);
+        let body = serde_json::to_vec(chunk).map_err(KvError::Serialize)?;
+        self.check_before_write(&body)?;
+        debug_bytes("PUT", &url, &body);
+
+        let resp = self
+            .send(
+                "write_multiple",
+                None,
+                self.client
+                    .put(url)
+                    .headers((*self.header_map).clone())
+                    .body(body),
+            )
+            .await?;
+
+        handle_response(resp)
+            .await
+            .map_err(|e| e.denied_as("write"))?;
+
+        Ok(())
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(
+                method = "POST",
+                path = "bulk/delete",
+                namespace_id = %self.namespace_id,
+                key = %key.as_ref(),
+                status,
+            )
+        )
+    )]
+    pub async fn delete(&self, key: impl AsRef<str>) -> Result<(), KvError> {
+        let key = key.as_ref();
+        let url = format!("{}/bulk/delete", self.url);
+        let payload = json!([key]);
+        debug_body("POST", &url, &payload);
+
+        let resp = self
+            .send(
+                "delete",
+                Some(key),
+                self.client
+                    .post(url)
+                    .headers((*self.header_map).clone())
+                    .json(&payload),
+            )
+            .await?;
+
+        handle_response(resp)
+            .await
+            .map_err(|e| e.denied_as("delete"))?;
+
+        Ok(())
+    }
+
+    /// Deletes `key` and returns whether it existed beforehand.
+    ///
+    /// Existence is checked with a separate metadata request first, so a concurrent write
+    /// or delete between the two calls can make the result stale.
+    pub async fn delete_checked(&self, key: impl AsRef<str>) -> Result<bool, KvError> {
+        let key = key.as_ref();
+        if !self.exists(key).await? {
+            return Ok(false);
+        }
+        self.delete(key).await?;
+        Ok(true)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(
+                method = "POST",
+                path = "bulk/delete",
+                namespace_id = %self.namespace_id,
+                len,
+                status,
+            )
+        )
+    )]
+    pub async fn delete_multiple(
+        &self,
+        keys: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> Result<(), KvError> {
+        let url = format!("{}/bulk/delete", self.url);
+        let keys = keys
+            .into_iter()
+            .map(|key| key.as_ref().to_string())
+            .collect::<Vec<_>>();
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("len", keys.len());
+        let payload = json!(keys);
+        debug_body("POST", &url, &payload);
+
+        let resp = self
+            .send(
+                "delete_multiple",
+                None,
+                self.client
+                    .post(url)
+                    .headers((*self.header_map).clone())
+                    .json(&payload),
+            )
+            .await?;
+
+        handle_response(resp)
+            .await
+            .map_err(|e| e.denied_as("delete"))?;
+
+        Ok(())
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(
+                method = "DELETE",
+                path = "values",
+                namespace_id = %self.namespace_id,
+                key = %key,
+                status,
+            )
+        )
+    )]
+    async fn delete_value(&self, key: &str) -> Result<(), KvError> {
+        let url = format!("{}/values/{}", self.url, encode_key(key));
+
+        let resp = self
+            .send(
+                "delete",
+                Some(key),
+                self.client.delete(url).headers((*self.header_map).clone()),
+            )
+            .await?;
+
+        handle_response(resp)
+            .await
+            .map_err(|e| e.denied_as("delete"))?;
+
+        Ok(())
+    }
+
+    /// Deletes each key with its own request, up to 4 at a time, and returns every key's
+    /// outcome in input order.
+    pub async fn delete_many_individually(
+        &self,
+        keys: &[&str],
+    ) -> Vec<(String, Result<(), KvError>)> {
+        stream::iter(
+            keys.iter()
+                .map(|key| async move { (key.to_string(), self.delete_value(key).await) }),
+        )
+        .buffered(DEFAULT_CONCURRENCY)
+        .collect()
+        .await
+    }
+
+    async fn fetch_keys_page(&self, cursor: &str) -> Result<(Vec<KeyInfo>, String, u64), KvError> {
+        let opts = ListOptions {
+            cursor: Some(cursor.to_string()),
+            ..ListOptions::default()
+        };
+        let (keys, result_info) = self.fetch_keys(&opts).await?;
+        let count = result_info.count;
+
+        Ok((keys, result_info.next_cursor(), count))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(
+                method = "GET",
+                path = "keys",
+                namespace_id = %self.namespace_id,
+                cursor = opts.cursor.as_deref().unwrap_or_default(),
+                status,
+            )
+        )
+    )]
+    async fn fetch_keys(&self, opts: &ListOptions) -> Result<(Vec<KeyInfo>, ResultInfo), KvError> {
+        let mut query = Vec::new();
+        if let Some(cursor) = opts.cursor.as_deref().filter(|cursor| !cursor.is_empty()) {
+            query.push(("cursor", cursor.to_string()));
+        }
+        if let Some(prefix) = &opts.prefix {
+            query.push(("prefix", prefix.clone()));
+        }
+        if let Some(limit) = opts.limit {
+            query.push(("limit", limit.to_string()));
+        }
+
+        let url = format!("{}/keys", self.url);
+        let resp = self
+            .send(
+                "list_keys",
+                None,
+                self.client
+                    .get(url)
+                    .query(&query)
+                    .headers((*self.header_map).clone()),
+            )
+            .await?;
+        let resp_json = handle_response(resp).await?;
+
+        let resp = parse_response::<Vec<KeyInfo>>(resp_json)?;
+        let result_info = resp.result_info.ok_or_else(|| {
+            KvError::MalformedResponse("No result_info found in response.".to_string())
+        })?;
+
+        Ok((resp.result, result_info))
+    }
+
+    /// Fetches a single page of keys, exposing the cursor so a listing can be resumed
+    /// later, e.g. after a restart.
+    pub async fn list_keys_page(&self, opts: ListOptions) -> Result<KeysPage, KvError> {
+        let (keys, result_info) = self.fetch_keys(&opts).await?;
+        let list_complete = result_info.is_complete();
+        let cursor = if list_complete {
+            None
+        } else {
+            Some(result_info.cursor)
+        };
+
+        Ok(KeysPage {
+            keys,
+            cursor,
+            list_complete,
+        })
+    }
+
+    pub async fn list_all_keys(&self) -> Result<Vec<String>, KvError> {
+        self.list_all_keys_with_count().await.map(|(keys, _)| keys)
+    }
+
+    /// Like `list_all_keys`, but reuses the previous listing if it is younger than `ttl`.
+    ///
+    /// The cache is shared by clones of this client and is not invalidated by writes or
+    /// deletes, so keys changed within `ttl` may be missing or stale. Not available on
+    /// wasm32, which has no monotonic clock.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn list_all_keys_cached(&self, ttl: Duration) -> Result<Vec<String>, KvError> {
+        if let Some((fetched_at, keys)) = &*self.keys_cache.lock().unwrap() {
+            if fetched_at.elapsed() < ttl {
+                return Ok(keys.clone());
+            }
+        }
+
+        let keys = self.list_all_keys().await?;
+        *self.keys_cache.lock().unwrap() = Some((Instant::now(), keys.clone()));
+        Ok(keys)
+    }
+
+    pub async fn list_all_keys_with_count(&self) -> Result<(Vec<String>, u64), KvError> {
+        collect_key_pages(self.key_pager(), |cursor| async move {
+            let opts = ListOptions {
+                cursor: Some(cursor),
+                ..ListOptions::default()
+            };
+            self.fetch_keys(&opts).await
+        })
+        .await
+    }
+
+    /// Lists every key like `list_all_keys`, checking `token` before each page request.
+    ///
+    /// On cancellation the keys collected so far are returned together with the cursor
+    /// of the next page, which can be used to resume the listing. The cursor is `None`
+    /// when the listing ran to completion.
+    #[cfg(feature = "cancellation")]
+    pub async fn list_all_keys_cancellable(
+        &self,
+        token: &CancellationToken,
+    ) -> Result<(Vec<String>, Option<String>), KvError> {
+        let mut keys = Vec::new();
+        let mut pager = self.key_pager();
+        loop {
+            if token.is_cancelled() {
+                return Ok((keys, Some(pager.cursor().to_string())));
+            }
+
+            let (page_keys, cursor_tmp, _cursor_count) =
+                self.fetch_keys_page(pager.cursor()).await?;
+            keys.extend(page_keys.into_iter().map(|key| key.name));
+
+            if !pager.advance(cursor_tmp)? {
+                break;
+            }
+        }
+        Ok((keys, None))
+    }
+
+    /// Streams every key with its expiration and metadata, fetching one page at a time
+    /// as the stream is polled instead of buffering the whole namespace.
+    pub fn keys_with_metadata_stream(&self) -> impl Stream<Item = Result<KeyInfo, KvError>> + '_ {
+        stream::try_unfold(Some(self.key_pager()), move |pager| async move {
+            let mut pager = match pager {
+                Some(pager) => pager,
+                None => return Ok::<_, KvError>(None),
+            };
+
+            let (page_keys, cursor_tmp, _cursor_count) =
+                self.fetch_keys_page(pager.cursor()).await?;
+            let next = if pager.advance(cursor_tmp)? {
+                Some(pager)
+            } else {
+                None
+            };
+
+            Ok(Some((stream::iter(page_keys.into_iter().map(Ok)), next)))
+        })
+        .try_flatten()
+    }
+
+    pub async fn count_keys(&self) -> Result<u64, KvError> {
+        self.list_all_keys_with_count()
+            .await
+            .map(|(_, count)| count)
+    }
+
+    pub async fn clear(&self) -> Result<usize, KvError> {
+        self.clear_with_progress(|_, _| {}).await
+    }
+
+    /// Like `clear`, but calls `progress(deleted, total)` after each bulk chunk.
+    pub async fn clear_with_progress(
+        &self,
+        progress: impl Fn(usize, usize),
+    ) -> Result<usize, KvError> {
+        let keys = self.list_all_keys().await?;
+        let total = keys.len();
+        let mut deleted = 0;
+
+        for chunk in keys.chunks(BULK_LIMIT) {
+            self.delete_multiple(chunk).await?;
+            deleted += chunk.len();
+            progress(deleted, total);
+        }
+
+        Ok(total)
+    }
+
+    /// Deletes every key starting with `prefix` in bulk chunks and returns how many were
+    /// deleted.
+    pub async fn clear_prefix(&self, prefix: &str) -> Result<usize, KvError> {
+        let mut keys = Vec::new();
+        let mut pager = self.key_pager();
+        loop {
+            let opts = ListOptions {
+                prefix: Some(prefix.to_string()),
+                limit: None,
+                cursor: Some(pager.cursor().to_string()),
+            };
+            let page = self.list_keys_page(opts).await?;
+            keys.extend(page.keys.into_iter().map(|key| key.name));
+
+            if !pager.advance(page.cursor.unwrap_or_default())? {
+                break;
+            }
+        }
+
+        for chunk in keys.chunks(BULK_LIMIT) {
+            self.delete_multiple(chunk).await?;
+        }
+
+        Ok(keys.len())
+    }
+
+    /// Copies every key of this namespace into `dest`, preserving metadata and expiration,
+    /// and returns the number of keys copied.
+    ///
+    /// Keys are read one listing page at a time with bounded concurrency and written in
+    /// bulk chunks, so the whole namespace is never held in memory. Keys that disappear
+    /// between being listed and being read are skipped, and so are keys expiring within
+    /// 60 seconds: Cloudflare refuses such expirations, and dropping the expiration
+    /// instead would make a dying key permanent in `dest`.
+    pub async fn copy_all_to(&self, dest: &KvNamespaceClient) -> Result<usize, KvError> {
+        self.copy_keys_to(dest, None, |_, _| {}).await
+    }
+
+    /// Like `copy_all_to`, but calls `progress(read, total)` after each listing page.
+    ///
+    /// `total` comes from an extra `count_keys` pass made before copying starts, so it can
+    /// drift from `read` if the namespace changes while the copy runs.
+    pub async fn copy_all_to_with_progress(
+        &self,
+        dest: &KvNamespaceClient,
+        progress: impl Fn(usize, usize),
+    ) -> Result<usize, KvError> {
+        let total = self.count_keys().await? as usize;
+        self.copy_keys_to(dest, Some(total), progress).await
+    }
+
+    // `progress` is only called when `total` is known, so plain `copy_all_to` doesn't
+    // pay for a counting pass.
+    async fn copy_keys_to(
+        &self,
+        dest: &KvNamespaceClient,
+        total: Option<usize>,
+        progress: impl Fn(usize, usize),
+    ) -> Result<usize, KvError> {
+        let mut read = 0;
+        let mut copied = 0;
+        let mut batch = Vec::new();
+        let mut pager = self.key_pager();
+        loop {
+            let (page_keys, cursor_tmp, _cursor_count) =
+                self.fetch_keys_page(pager.cursor()).await?;
+            read += page_keys.len();
+
+            let requests = stream::iter(page_keys.into_iter().map(|key| async move {
+                if validate_expiration(&key.name, key.expiration, None).is_err() {
+                    return Ok(None);
+                }
+                match self.get_bytes(&key.name).await {
+                    Ok(value) => {
+                        let mut payload = KvRequest::from_bytes(&key.name, &value);
+                        payload.expiration = key.expiration;
+                        payload.metadata = key.metadata;
+                        Ok(Some(payload))
+                    }
+                    Err(KvError::NotFound { .. }) => Ok(None),
+                    Err(e) => Err(e),
+                }
+            }))
+            .buffer_unordered(DEFAULT_CONCURRENCY)
+            .collect::<Vec<_>>()
+            .await;
+
+            for payload in requests {
+                if let Some(payload) = payload? {
+                    batch.push(payload);
+                }
+            }
+
+            if batch.len() >= BULK_LIMIT {
+                drop_expiring(&mut batch);
+                copied += batch.len();
+                dest.write_multiple(std::mem::take(&mut batch)).await?;
+            }
+
+            if let Some(total) = total {
+                progress(read, total);
+            }
+
+            if !pager.advance(cursor_tmp)? {
+                break;
+            }
+        }
+
+        drop_expiring(&mut batch);
+        if !batch.is_empty() {
+            copied += batch.len();
+            dest.write_multiple(batch).await?;
+        }
+
+        Ok(copied)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(
+                method = "GET",
+                path = "metadata",
+                namespace_id = %self.namespace_id,
+                key = %key.as_ref(),
+                status,
+            )
+        )
+    )]
+    pub async fn read_metadata(&self, key: impl AsRef<str>) -> Result<Value, KvError> {
+        let key = key.as_ref();
+        let url = format!("{}/metadata/{}", self.url, encode_key(key));
+
+        let resp = self
+            .send(
+                "read_metadata",
+                Some(key),
+                self.client.get(url).headers((*self.header_map).clone()),
+            )
+            .await?;
+
+        let resp = check_value_response(key, resp).await?;
+        let resp_json = handle_response(resp).await?;
+
+        match resp_json.get("result") {
+            Some(result) => Ok(result.clone()),
+            None => Err(KvError::MalformedResponse(
+                "No result found in response.".to_string(),
+            )),
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(
+                method = "GET",
+                path = "metadata",
+                namespace_id = %self.namespace_id,
+                key = %key.as_ref(),
+                status,
+            )
+        )
+    )]
+    pub async fn exists(&self, key: impl AsRef<str>) -> Result<bool, KvError> {
+        let key = key.as_ref();
+        let url = format!("{}/metadata/{}", self.url, encode_key(key));
+
+        let resp = self
+            .send(
+                "exists",
+                Some(key),
+                self.client.get(url).headers((*self.header_map).clone()),
+            )
+            .await?;
+
+        if resp.status().as_u16() == 404 {
+            return Ok(false);
+        }
+
+        handle_response(resp).await?;
+
+        Ok(true)
+    }
+
+    pub async fn exists_multiple(&self, keys: &[&str]) -> Result<HashMap<String, bool>, KvError> {
+        let results = stream::iter(keys.iter().map(|key| async move {
+            self.exists(key)
+                .await
+                .map(|exists| (key.to_string(), exists))
+        }))
+        .buffer_unordered(DEFAULT_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await;
+
+        results.into_iter().collect()
+    }
+
+    /// Reads the metadata of each key, up to 4 at a time, and returns every key's outcome
+    /// in input order.
+    pub async fn read_metadata_many(&self, keys: &[&str]) -> Vec<(String, Result<Value, KvError>)> {
+        stream::iter(
+            keys.iter()
+                .map(|key| async move { (key.to_string(), self.read_metadata(key).await) }),
+        )
+        .buffered(DEFAULT_CONCURRENCY)
+        .collect()
+        .await
+    }
+
+    /// Reads the content type stored by `put_with_content_type`, or `None` if the key's
+    /// metadata has none.
+    pub async fn get_content_type(&self, key: impl AsRef<str>) -> Result<Option<String>, KvError> {
+        let metadata = self.read_metadata(key).await?;
+
+        match metadata.get(CONTENT_TYPE_FIELD) {
+            Some(Value::String(content_type)) => Ok(Some(content_type.clone())),
+            Some(Value::Null) | None => Ok(None),
+            Some(value) => Err(KvError::UnexpectedType(format!(
+                "The metadata field '{}' is not a string: {}",
+                CONTENT_TYPE_FIELD, value
+            ))),
+        }
+    }
+
+    pub async fn read_metadata_as<T: DeserializeOwned>(
+        &self,
+        key: impl AsRef<str>,
+    ) -> Result<T, KvError> {
+        let metadata = self.read_metadata(key).await?;
+        serde_json::from_value(metadata).map_err(KvError::Deserialize)
+    }
+
+    /// Reads a numeric metadata field as `u64`, accepting integers and whole-number floats.
+    ///
+    /// Returns `Ok(None)` when the key has no metadata or the field is absent.
+    pub async fn metadata_u64(
+        &self,
+        key: impl AsRef<str>,
+        field: &str,
+    ) -> Result<Option<u64>, KvError> {
+        let metadata = self.read_metadata(key).await?;
+
+        let value = match metadata.get(field) {
+            Some(Value::Null) | None => return Ok(None),
+            Some(value) => value,
+        };
+
+        if let Some(value) = value.as_u64() {
+            return Ok(Some(value));
+        }
+
+        match value.as_f64() {
+            Some(value) if value >= 0.0 && value <= u64::MAX as f64 && value.fract() == 0.0 => {
+                Ok(Some(value as u64))
+            }
+            _ => Err(KvError::UnexpectedType(format!(
+                "The metadata field '{}' is not an unsigned integer: {}",
+                field, value
+            ))),
+        }
+    }
+
+    async fn fetch_value(&self, key: &str) -> Result<reqwest::Response, KvError> {
+        let url = format!("{}/values/{}", self.url, encode_key(key));
+
+        let resp = self
+            .send(
+                "get",
+                Some(key),
+                self.client.get(url).headers((*self.header_map).clone()),
+            )
             .await?;
 
-        if resp.status().is_success() == false {
-            warn!("Cloudflare returned an ERROR httpcode.")
-        }
+        check_value_response(key, resp).await
+    }
+
+    /// Reads the value of `key` as text. Invalid UTF-8 is replaced with U+FFFD and a
+    /// leading byte order mark is stripped; use `get_bytes` for the exact stored bytes.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(
+                method = "GET",
+                path = "values",
+                namespace_id = %self.namespace_id,
+                key = %key.as_ref(),
+                status,
+            )
+        )
+    )]
+    pub async fn get(&self, key: impl AsRef<str>) -> Result<String, KvError> {
+        let key = key.as_ref();
+        let resp = self.fetch_value(key).await?;
+
+        let resp_value = resp.text().await?;
+
+        Ok(resp_value)
+    }
+
+    /// Like `get`, but fails with `KvError::Timeout` if the read takes longer than
+    /// `timeout`. Not available on wasm32, where tokio has no timer.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn get_with_timeout(
+        &self,
+        key: impl AsRef<str>,
+        timeout: Duration,
+    ) -> Result<String, KvError> {
+        tokio::time::timeout(timeout, self.get(key))
+            .await
+            .map_err(|_| KvError::Timeout(timeout))?
+    }
+
+    pub async fn get_json<T: DeserializeOwned>(&self, key: impl AsRef<str>) -> Result<T, KvError> {
+        let value = self.get_bytes(key).await?;
+        serde_json::from_slice(&value).map_err(KvError::Deserialize)
+    }
 
-        let resp_json = resp.json::<Value>().await?;
-        if check_success(resp_json.clone()).await? == false {
-            return Err(convert_string_to_error(resp_json.to_string().as_str()).await);
-        }
+    /// Reads each key, up to 4 at a time, and returns every key's outcome in input order.
+    pub async fn get_many(&self, keys: &[&str]) -> Vec<(String, Result<String, KvError>)> {
+        self.get_many_with_progress(keys, |_, _| {}).await
+    }
 
-        Ok(())
+    /// Like `get_many`, but calls `progress(completed, total)` as each read finishes.
+    pub async fn get_many_with_progress(
+        &self,
+        keys: &[&str],
+        progress: impl Fn(usize, usize),
+    ) -> Vec<(String, Result<String, KvError>)> {
+        let total = keys.len();
+        let mut completed = 0;
+        stream::iter(
+            keys.iter()
+                .map(|key| async move { (key.to_string(), self.get(key).await) }),
+        )
+        .buffered(DEFAULT_CONCURRENCY)
+        .inspect(|_| {
+            completed += 1;
+            progress(completed, total);
+        })
+        .collect()
+        .await
     }
 
-    pub async fn write_multiple(
+    /// Like `get_many`, but deserializes each value from JSON.
+    pub async fn get_many_json<T: DeserializeOwned>(
         &self,
-        payload: Vec<KvRequest>,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let url = format!("{}/bulk", self.url);
+        keys: &[&str],
+    ) -> Vec<(String, Result<T, KvError>)> {
+        stream::iter(
+            keys.iter()
+                .map(|key| async move { (key.to_string(), self.get_json(key).await) }),
+        )
+        .buffered(DEFAULT_CONCURRENCY)
+        .collect()
+        .await
+    }
+
+    /// Reads the value of `key` only if its ETag differs from `etag`, returning `None` when
+    /// it is unchanged and otherwise the value with its new ETag (empty if none was sent).
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(
+                method = "GET",
+                path = "values",
+                namespace_id = %self.namespace_id,
+                key = %key.as_ref(),
+                status,
+            )
+        )
+    )]
+    pub async fn get_if_none_match(
+        &self,
+        key: impl AsRef<str>,
+        etag: &str,
+    ) -> Result<Option<(String, String)>, KvError> {
+        let key = key.as_ref();
+        let url = format!("{}/values/{}", self.url, encode_key(key));
+
         let resp = self
-            .client
-            .put(url)
-            .headers(self.header_map.clone())
-            .json(&payload)
-            .send()
+            .send(
+                "get_if_none_match",
+                Some(key),
+                self.client
+                    .get(url)
+                    .headers((*self.header_map).clone())
+                    .header(IF_NONE_MATCH, etag),
+            )
             .await?;
 
-        if resp.status().is_success() == false {
-            warn!("Cloudflare returned an ERROR httpcode.")
+        if resp.status() == StatusCode::NOT_MODIFIED {
+            record_status(304);
+            return Ok(None);
         }
 
-        let resp_json = resp.json::<Value>().await?;
+        let resp = check_value_response(key, resp).await?;
+        let new_etag = resp
+            .headers()
+            .get(ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
 
-        if check_success(resp_json.clone()).await? == false {
-            return Err(convert_string_to_error(resp_json.to_string().as_str()).await);
-        }
+        let resp_value = resp.text().await?;
 
-        Ok(())
+        Ok(Some((resp_value, new_etag)))
     }
 
-    pub async fn delete(&self, key: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let url = format!("{}/bulk/delete", self.url);
-        let payload = json!([key]);
-
-        let resp = self
-            .client
-            .post(url)
-            .headers(self.header_map.clone())
-            .json(&payload)
-            .send()
-            .await?;
+    /// Returns the response of a value read without consuming its body, for streaming large
+    /// values or reading headers. A missing key still fails with `KvError::NotFound`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(
+                method = "GET",
+                path = "values",
+                namespace_id = %self.namespace_id,
+                key = %key.as_ref(),
+                status,
+            )
+        )
+    )]
+    pub async fn get_raw(&self, key: impl AsRef<str>) -> Result<reqwest::Response, KvError> {
+        self.fetch_value(key.as_ref()).await
+    }
 
-        if resp.status().is_success() == false {
-            warn!("Cloudflare returned an ERROR httpcode.")
-        }
+    /// Reads the value of `key` as a stream of chunks, so large values can be forwarded
+    /// without buffering them in memory.
+    #[cfg(feature = "stream")]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(
+                method = "GET",
+                path = "values",
+                namespace_id = %self.namespace_id,
+                key = %key.as_ref(),
+                status,
+            )
+        )
+    )]
+    pub async fn get_stream(
+        &self,
+        key: impl AsRef<str>,
+    ) -> Result<impl Stream<Item = Result<bytes::Bytes, KvError>>, KvError> {
+        let resp = self.fetch_value(key.as_ref()).await?;
+        Ok(resp.bytes_stream().map_err(KvError::from))
+    }
 
-        let resp_json = resp.json::<Value>().await?;
+    /// Reads the value of `key` along with the Unix timestamp it expires at, or `None` if
+    /// it never expires.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(
+                method = "GET",
+                path = "values",
+                namespace_id = %self.namespace_id,
+                key = %key.as_ref(),
+                status,
+            )
+        )
+    )]
+    pub async fn get_with_expiration(
+        &self,
+        key: impl AsRef<str>,
+    ) -> Result<(String, Option<u64>), KvError> {
+        let resp = self.fetch_value(key.as_ref()).await?;
+        let expiration = expiration_header(&resp);
 
-        if check_success(resp_json.clone()).await? == false {
-            return Err(convert_string_to_error(resp_json.to_string().as_str()).await);
-        }
+        let resp_value = resp.text().await?;
 
-        Ok(())
+        Ok((resp_value, expiration))
     }
 
-    pub async fn delete_multiple(&self, keys: Vec<&str>) -> Result<(), Box<dyn std::error::Error>> {
-        let url = format!("{}/bulk/delete", self.url);
-        let payload = json!(keys);
+    pub async fn put_raw_bytes(
+        &self,
+        key: impl AsRef<str>,
+        bytes: &[u8],
+        opts: WriteOptions,
+    ) -> Result<(), KvError> {
+        let payload = KvRequest::from_bytes(key.as_ref(), bytes).with_write_options(opts);
+        self.write(payload).await
+    }
 
-        let resp = self
-            .client
-            .post(url)
-            .headers(self.header_map.clone())
-            .json(&payload)
-            .send()
-            .await?;
+    /// Replaces the metadata of `key`, keeping its value and expiration.
+    ///
+    /// KV has no metadata-only update, so this reads the current value and writes it
+    /// back with the new metadata. A write to the key made in between is overwritten.
+    pub async fn update_metadata(
+        &self,
+        key: impl AsRef<str>,
+        metadata: Value,
+    ) -> Result<(), KvError> {
+        let key = key.as_ref();
+        let resp = self.fetch_value(key).await?;
+        let expiration = expiration_header(&resp);
+        let value = resp.bytes().await?;
+
+        let mut payload = KvRequest::from_bytes(key, &value).metadata(metadata);
+        payload.expiration = expiration;
+
+        self.write(payload).await
+    }
 
-        if resp.status().is_success() == false {
-            warn!("Cloudflare returned an ERROR httpcode.")
+    pub async fn get_with_metadata(
+        &self,
+        key: impl AsRef<str>,
+    ) -> Result<(String, Option<Value>), KvError> {
+        let key = key.as_ref();
+        let (value, metadata) = futures::try_join!(self.get(key), self.read_metadata(key))?;
+
+        match metadata {
+            Value::Null => Ok((value, None)),
+            metadata => Ok((value, Some(metadata))),
         }
+    }
 
-        let resp_json = resp.json::<Value>().await?;
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(
+                method = "GET",
+                path = "values",
+                namespace_id = %self.namespace_id,
+                key = %key.as_ref(),
+                status,
+            )
+        )
+    )]
+    pub async fn get_bytes(&self, key: impl AsRef<str>) -> Result<Vec<u8>, KvError> {
+        let key = key.as_ref();
+        let resp = self.fetch_value(key).await?;
 
-        if check_success(resp_json.clone()).await? == false {
-            return Err(convert_string_to_error(resp_json.to_string().as_str()).await);
-        }
+        let resp_value = resp.bytes().await?;
 
-        Ok(())
+        Ok(resp_value.to_vec())
     }
+}
 
-    pub async fn list_all_keys(&self) -> Result<Vec<String>, Box<dyn std::error::Error>> {
-        let url = format!("{}/keys", self.url);
-        let mut keys = Vec::new();
-        let mut cursor = "".to_string();
-        loop {
-            let url = format!("{}?cursor={}", url, cursor);
-            let resp = self
-                .client
-                .get(url.clone())
-                .headers(self.header_map.clone())
-                .send()
-                .await?;
-            if resp.status().is_success() == false {
-                warn!("Cloudflare returned an ERROR httpcode.")
-            }
-            let resp_json = resp.json::<Value>().await?;
-
-            if check_success(resp_json.clone()).await? == false {
-                return Err(convert_string_to_error(resp_json.to_string().as_str()).await);
-            }
-
-            let results = match resp_json.get("result") {
-                Some(result) => match result.as_array() {
-                    Some(result) => result,
-                    None => {
-                        return Err(convert_string_to_error("No result found in response.").await);
-                    }
-                },
-                None => {
-                    return Err(convert_string_to_error("No result found in response.").await);
-                }
-            };
+/// `Send` everywhere except wasm32, where reqwest's futures are not `Send`.
+#[cfg(not(target_arch = "wasm32"))]
+pub trait MaybeSend: Send {}
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send> MaybeSend for T {}
+
+/// `Send` everywhere except wasm32, where reqwest's futures are not `Send`.
+#[cfg(target_arch = "wasm32")]
+pub trait MaybeSend {}
+#[cfg(target_arch = "wasm32")]
+impl<T> MaybeSend for T {}
+
+/// The core key-value operations, so code can be written against either a real
+/// `KvNamespaceClient` or, with the `test-util` feature, a `mock::MockKvStore`.
+pub trait KvStore {
+    fn get(&self, key: &str) -> impl Future<Output = Result<String, KvError>> + MaybeSend;
+    fn write(&self, payload: KvRequest) -> impl Future<Output = Result<(), KvError>> + MaybeSend;
+    fn delete(&self, key: &str) -> impl Future<Output = Result<(), KvError>> + MaybeSend;
+    fn list_all_keys(&self) -> impl Future<Output = Result<Vec<String>, KvError>> + MaybeSend;
+}
 
-            for result in results {
-                match result.get("name") {
-                    Some(name) => {
-                        let name = match name.as_str() {
-                            Some(name) => name,
-                            None => {
-                                return Err(
-                                    convert_string_to_error("No name found in response.").await
-                                );
-                            }
-                        };
-                        keys.push(name.to_string());
-                    }
-                    None => {
-                        return Err(convert_string_to_error("No name found in response.").await);
-                    }
-                }
-            }
+impl KvStore for KvNamespaceClient {
+    async fn get(&self, key: &str) -> Result<String, KvError> {
+        KvNamespaceClient::get(self, key).await
+    }
 
-            let (cursor_tmp, _cursor_count) = match resp_json.get("result_info") {
-                Some(result_info) => {
-                    let cursor_tmp = match result_info.get("cursor") {
-                        Some(cursor) => match cursor.as_str() {
-                            Some(cursor) => cursor.to_string(),
-                            None => {
-                                return Err(convert_string_to_error(
-                                    "No cursor found in response.",
-                                )
-                                .await);
-                            }
-                        },
-                        None => {
-                            return Err(
-                                convert_string_to_error("No cursor found in response.").await
-                            );
-                        }
-                    };
-                    let cursor_count = match result_info.get("count") {
-                        Some(count) => match count.as_u64() {
-                            Some(count) => count,
-                            None => {
-                                return Err(
-                                    convert_string_to_error("No count found in response.").await
-                                );
-                            }
-                        },
-                        None => {
-                            return Err(
-                                convert_string_to_error("No count found in response.").await
-                            );
-                        }
-                    };
-                    (cursor_tmp, cursor_count)
-                }
-                None => {
-                    return Err(convert_string_to_error("No result_info found in response.").await);
-                }
-            };
+    async fn write(&self, payload: KvRequest) -> Result<(), KvError> {
+        KvNamespaceClient::write(self, payload).await
+    }
 
+    async fn delete(&self, key: &str) -> Result<(), KvError> {
+        KvNamespaceClient::delete(self, key).await
+    }
 
-            if cursor_tmp.is_empty() {
-                break;
-            } else {
-                cursor = cursor_tmp;
-                continue;
-            }
-        }
-        Ok(keys)
+    async fn list_all_keys(&self) -> Result<Vec<String>, KvError> {
+        KvNamespaceClient::list_all_keys(self).await
     }
+}
 
-    pub async fn read_metadata(&self, key: &str) -> Result<Value, Box<dyn std::error::Error>> {
-        let url = format!("{}/metadata/{}", self.url, key);
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeyInfo {
+    pub name: String,
+    pub expiration: Option<u64>,
+    pub metadata: Option<Value>,
+}
 
-        let resp = self
-            .client
-            .get(url)
-            .headers(self.header_map.clone())
-            .send()
-            .await?;
+/// Storage usage of a namespace, as returned by `KvNamespaceClient::storage_usage`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageStats {
+    pub key_count: u64,
+    pub byte_count: u64,
+}
 
-        if resp.status().is_success() == false {
-            warn!("Cloudflare returned an ERROR httpcode.")
-        }
+/// Filters and position for a single `list_keys_page` call. Cloudflare returns up to
+/// 1000 keys per page when `limit` is unset.
+#[derive(Clone, Debug, Default)]
+pub struct ListOptions {
+    pub prefix: Option<String>,
+    pub limit: Option<u32>,
+    pub cursor: Option<String>,
+}
 
-        let resp_json = resp.json::<Value>().await?;
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeysPage {
+    pub keys: Vec<KeyInfo>,
+    /// Cursor to pass in `ListOptions` for the next page, `None` on the last page.
+    pub cursor: Option<String>,
+    pub list_complete: bool,
+}
 
-        if check_success(resp_json.clone()).await? == false {
-            return Err(convert_string_to_error(resp_json.to_string().as_str()).await);
-        }
+#[derive(Clone, Debug, Default)]
+pub struct WriteOptions {
+    pub expiration: Option<u64>,
+    pub expiration_ttl: Option<u64>,
+    pub metadata: Option<Value>,
+}
 
-        match resp_json.get("result") {
-            Some(result) => Ok(result.clone()),
-            None => {
-                Err(convert_string_to_error("No result found in response.").await)
-            }
+// Cloudflare rejects expirations less than 60 seconds in the future with an
+// opaque error, so catch them before sending. The wasm32 target has no system
+// clock to compare absolute timestamps against.
+fn validate_expiration(
+    key: &str,
+    expiration: Option<u64>,
+    expiration_ttl: Option<u64>,
+) -> Result<(), KvError> {
+    if let Some(ttl) = expiration_ttl {
+        if ttl < MIN_EXPIRATION_TTL {
+            return Err(KvError::InvalidExpiration(format!(
+                "expiration_ttl of key '{}' is {} seconds, the minimum is {}",
+                key, ttl, MIN_EXPIRATION_TTL
+            )));
         }
     }
 
-    pub async fn get(&self, key: &str) -> Result<String, Box<dyn std::error::Error>> {
-        let url = format!("{}/values/{}", self.url, key);
-
-        let resp = self
-            .client
-            .get(url)
-            .headers(self.header_map.clone())
-            .send()
-            .await?;
-
-        if resp.status().is_success() == false {
-            warn!("Cloudflare returned an ERROR httpcode.")
-        }
-
-        if resp.status().as_u16() == 404 {
-            let resp_json = resp.json::<Value>().await?;
-            log::error!("Key: {} Not Found", key);
-            return Err(convert_string_to_error(resp_json.to_string().as_str()).await);
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(expiration) = expiration {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|now| now.as_secs())
+            .unwrap_or(0);
+        if expiration < now + MIN_EXPIRATION_TTL {
+            return Err(KvError::InvalidExpiration(format!(
+                "expiration {} of key '{}' is not at least {} seconds after the current time {}",
+                expiration, key, MIN_EXPIRATION_TTL, now
+            )));
         }
-
-        let resp_value = resp.text().await?;
-
-        Ok(resp_value)
     }
+    #[cfg(target_arch = "wasm32")]
+    let _ = expiration;
+
+    Ok(())
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct KvRequest {
     key: String,
     value: String,
@@ -555,6 +3012,12 @@ pub struct KvRequest {
 }
 
 impl KvRequest {
+    pub fn builder(key: &str, value: &str) -> KvRequestBuilder {
+        KvRequestBuilder {
+            request: KvRequest::new(key, value),
+        }
+    }
+
     pub fn new(key: &str, value: &str) -> Self {
         KvRequest {
             key: key.to_string(),
@@ -566,6 +3029,28 @@ impl KvRequest {
         }
     }
 
+    /// Creates a request for binary data, base64-encoding `value` and setting the `base64` flag.
+    ///
+    /// Cloudflare decodes the value before storing it, so it can be read back with `get_bytes`.
+    pub fn from_bytes(key: &str, value: &[u8]) -> Self {
+        KvRequest {
+            key: key.to_string(),
+            value: base64::engine::general_purpose::STANDARD.encode(value),
+            base64: true,
+            expiration: None,
+            expiration_ttl: None,
+            metadata: None,
+        }
+    }
+
+    /// Creates a request storing `value` as compact JSON, the counterpart of `get_json`.
+    pub fn from_value(key: &str, value: &Value) -> Self {
+        KvRequest::new(key, &value.to_string())
+    }
+
+    /// Marks the value as base64-encoded. The value is sent unchanged, so it must already
+    /// hold base64 text; Cloudflare decodes it and rejects or corrupts anything else. To
+    /// store raw bytes, use `from_bytes` or `with_raw_bytes`, which encode them.
     pub fn enable_base64(&self) -> Self {
         KvRequest {
             base64: true,
@@ -577,6 +3062,37 @@ impl KvRequest {
         }
     }
 
+    /// Replaces the value with base64-encoded `value` and sets the `base64` flag, keeping
+    /// the key, expiration and metadata.
+    pub fn with_raw_bytes(&self, value: &[u8]) -> Self {
+        KvRequest {
+            base64: true,
+            key: self.key.clone(),
+            value: base64::engine::general_purpose::STANDARD.encode(value),
+            expiration: self.expiration,
+            expiration_ttl: self.expiration_ttl,
+            metadata: self.metadata.clone(),
+        }
+    }
+
+    /// Returns a copy of this request for `key`, keeping the value, expiration and metadata.
+    pub fn with_key(&self, key: &str) -> Self {
+        KvRequest {
+            key: key.to_string(),
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of this request with the plain-text `value`, clearing the `base64`
+    /// flag and keeping the key, expiration and metadata.
+    pub fn with_value(&self, value: &str) -> Self {
+        KvRequest {
+            base64: false,
+            value: value.to_string(),
+            ..self.clone()
+        }
+    }
+
     pub fn ttl_sec(&self, ttl_sec: u64) -> Self {
         KvRequest {
             base64: self.base64,
@@ -599,6 +3115,22 @@ impl KvRequest {
         }
     }
 
+    /// Expires the key after `duration`, rounded down to whole seconds.
+    ///
+    /// Cloudflare requires a TTL of at least 60 seconds; shorter ones are rejected on write.
+    pub fn expires_in(&self, duration: Duration) -> Self {
+        self.ttl_sec(duration.as_secs())
+    }
+
+    /// Expires the key at the given point in time.
+    ///
+    /// Cloudflare requires the expiration to be at least 60 seconds in the future; earlier
+    /// ones are rejected on write.
+    #[cfg(feature = "chrono")]
+    pub fn expires_at(&self, when: chrono::DateTime<chrono::Utc>) -> Self {
+        self.ttl_timestemp(when.timestamp().max(0) as u64)
+    }
+
     pub fn metadata(&self, metadata: Value) -> Self {
         KvRequest {
             base64: self.base64,
@@ -609,4 +3141,399 @@ impl KvRequest {
             metadata: Some(metadata),
         }
     }
+
+    fn with_write_options(mut self, opts: WriteOptions) -> Self {
+        self.expiration = opts.expiration;
+        self.expiration_ttl = opts.expiration_ttl;
+        self.metadata = opts.metadata;
+        self
+    }
+
+    // Size of the value Cloudflare stores, which for base64 requests is the decoded size.
+    fn value_len(&self) -> usize {
+        if !self.base64 {
+            return self.value.len();
+        }
+        let padding = self.value.bytes().rev().take_while(|&b| b == b'=').count();
+        (self.value.len() / 4 * 3).saturating_sub(padding)
+    }
+
+    // Checks everything that doesn't require decoding the value, so it's cheap enough
+    // to run on every write.
+    fn validate(&self) -> Result<(), KvError> {
+        if self.key.is_empty() || self.key.len() > MAX_KEY_LEN {
+            return Err(KvError::InvalidRequest(format!(
+                "key '{}' must be between 1 and {} bytes long",
+                self.key, MAX_KEY_LEN
+            )));
+        }
+
+        let value_len = self.value_len();
+        if value_len > MAX_VALUE_LEN {
+            return Err(KvError::InvalidRequest(format!(
+                "value of key '{}' is {} bytes, the maximum is {}",
+                self.key, value_len, MAX_VALUE_LEN
+            )));
+        }
+
+        if let Some(metadata) = &self.metadata {
+            let metadata_len = metadata.to_string().len();
+            if metadata_len > MAX_METADATA_LEN {
+                return Err(KvError::InvalidRequest(format!(
+                    "metadata of key '{}' is {} bytes, the maximum is {}",
+                    self.key, metadata_len, MAX_METADATA_LEN
+                )));
+            }
+        }
+
+        validate_expiration(&self.key, self.expiration, self.expiration_ttl)
+    }
+
+    pub fn metadata_typed<T: Serialize>(&self, metadata: &T) -> Result<Self, KvError> {
+        let metadata = serde_json::to_value(metadata).map_err(KvError::Serialize)?;
+        Ok(self.metadata(metadata))
+    }
+}
+
+/// Consuming builder for `KvRequest` that checks Cloudflare's limits once, in `build`.
+#[derive(Debug)]
+pub struct KvRequestBuilder {
+    request: KvRequest,
+}
+
+impl KvRequestBuilder {
+    /// Marks the value as base64-encoded binary data.
+    pub fn base64(mut self) -> Self {
+        self.request.base64 = true;
+        self
+    }
+
+    pub fn expiration_ttl(mut self, ttl_sec: u64) -> Self {
+        self.request.expiration_ttl = Some(ttl_sec);
+        self
+    }
+
+    pub fn expiration(mut self, timestamp: u64) -> Self {
+        self.request.expiration = Some(timestamp);
+        self
+    }
+
+    pub fn expires_in(self, duration: Duration) -> Self {
+        self.expiration_ttl(duration.as_secs())
+    }
+
+    pub fn metadata(mut self, metadata: Value) -> Self {
+        self.request.metadata = Some(metadata);
+        self
+    }
+
+    pub fn metadata_typed<T: Serialize>(self, metadata: &T) -> Result<Self, KvError> {
+        let metadata = serde_json::to_value(metadata).map_err(KvError::Serialize)?;
+        Ok(self.metadata(metadata))
+    }
+
+    /// Checks the key, value and metadata sizes, the expiration and, for base64
+    /// requests, that the value decodes, then returns the request.
+    pub fn build(self) -> Result<KvRequest, KvError> {
+        let request = self.request;
+        request.validate()?;
+
+        if request.base64
+            && base64::engine::general_purpose::STANDARD
+                .decode(&request.value)
+                .is_err()
+        {
+            return Err(KvError::InvalidRequest(format!(
+                "value of key '{}' is marked as base64 but is not valid base64",
+                request.key
+            )));
+        }
+
+        Ok(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NAMESPACE_ID: &str = "0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn debug_redacts_api_key() {
+        let client = KvClient::new("acct", "secret");
+        let debug = format!("{:?}", client);
+        assert!(!debug.contains("secret"), "{}", debug);
+        assert!(debug.contains("[REDACTED]"));
+
+        let namespace = KvNamespaceClient::new("acct", "secret", NAMESPACE_ID);
+        let debug = format!("{:?}", namespace);
+        assert!(!debug.contains("secret"), "{}", debug);
+        assert!(debug.contains("[REDACTED]"));
+    }
+
+    #[test]
+    fn namespace_url_matches_between_constructors() {
+        for account_id in ["acct", "acct/", "/acct/"] {
+            let kvclient = KvClient::new(account_id, "secret");
+            let direct = KvNamespaceClient::new(account_id, "secret", NAMESPACE_ID);
+            let shared = KvNamespaceClient::from_kvclient(&kvclient, NAMESPACE_ID).unwrap();
+            assert_eq!(direct.url, shared.url);
+            assert!(!direct.url.contains("//acct") && !direct.url.contains("acct//"));
+        }
+    }
+
+    #[test]
+    fn join_url_collapses_slashes() {
+        assert_eq!(join_url("https://a/b/", "/c"), "https://a/b/c");
+        assert_eq!(join_url("https://a/b", "c"), "https://a/b/c");
+    }
+
+    #[test]
+    fn encode_key_escapes_reserved_characters() {
+        assert_eq!(encode_key("path/to/thing").to_string(), "path%2Fto%2Fthing");
+        assert_eq!(encode_key("a b").to_string(), "a%20b");
+        assert_eq!(encode_key("a?b#c").to_string(), "a%3Fb%23c");
+        assert_eq!(encode_key("ключ").to_string(), "%D0%BA%D0%BB%D1%8E%D1%87");
+        assert_eq!(encode_key("a-b.c_d~e").to_string(), "a-b.c_d~e");
+    }
+
+    #[test]
+    fn api_error_messages_reads_string_messages() {
+        let body = r#"{"success":false,"errors":[],"messages":["first","second"]}"#;
+        assert_eq!(api_error_messages(body).as_deref(), Some("first; second"));
+    }
+
+    #[test]
+    fn api_error_messages_reads_code_objects() {
+        let body = r#"{"success":false,"errors":[],"messages":[{"code":10009,"message":"key not found"}]}"#;
+        assert_eq!(
+            api_error_messages(body).as_deref(),
+            Some("key not found (code 10009)")
+        );
+    }
+
+    #[test]
+    fn api_error_messages_merges_errors_and_messages() {
+        let body =
+            r#"{"success":false,"errors":[{"code":10000,"message":"denied"}],"messages":["hint"]}"#;
+        assert_eq!(
+            api_error_messages(body).as_deref(),
+            Some("denied (code 10000); hint")
+        );
+        assert_eq!(api_error_messages(r#"{"errors":[],"messages":[]}"#), None);
+    }
+
+    #[test]
+    fn key_listing_parses_keys_with_and_without_metadata() {
+        let resp_json = json!({
+            "success": true,
+            "errors": [],
+            "result": [
+                { "name": "a", "metadata": { "owner": "x" } },
+                { "name": "b", "expiration": 1_900_000_000u64 },
+                { "name": "c", "metadata": { "owner": "y" } },
+                { "name": "d" },
+            ],
+            "result_info": { "count": 4, "cursor": "" },
+        });
+
+        let keys = parse_response::<Vec<KeyInfo>>(resp_json).unwrap().result;
+        let metadata = keys
+            .iter()
+            .map(|key| key.metadata.is_some())
+            .collect::<Vec<_>>();
+        assert_eq!(metadata, [true, false, true, false]);
+        assert_eq!(keys[1].expiration, Some(1_900_000_000));
+        assert_eq!(keys[0].metadata, Some(json!({ "owner": "x" })));
+    }
+
+    fn result_info(cursor: &str, list_complete: Option<bool>) -> ResultInfo {
+        ResultInfo {
+            cursor: cursor.to_string(),
+            count: 0,
+            list_complete,
+        }
+    }
+
+    #[test]
+    fn list_complete_overrides_cursor() {
+        assert!(result_info("next", Some(true)).is_complete());
+        assert!(!result_info("next", Some(false)).is_complete());
+        assert!(!result_info("next", None).is_complete());
+        assert!(result_info("", None).is_complete());
+    }
+
+    fn key_infos(names: &[&str]) -> Vec<KeyInfo> {
+        names
+            .iter()
+            .map(|name| KeyInfo {
+                name: name.to_string(),
+                expiration: None,
+                metadata: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn list_keys_follows_cursor_until_complete() {
+        let pager = KvNamespaceClient::new("acct", "secret", NAMESPACE_ID).key_pager();
+        let mut requested = Vec::new();
+        let result = futures::executor::block_on(collect_key_pages(pager, |cursor| {
+            requested.push(cursor.clone());
+            async move {
+                Ok(match cursor.as_str() {
+                    "" => (key_infos(&["a", "b"]), result_info("page2", None)),
+                    _ => (key_infos(&["c"]), result_info("", None)),
+                })
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(requested, ["", "page2"]);
+        assert_eq!(result.0, ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn list_keys_stops_when_list_complete_despite_cursor() {
+        let pager = KvNamespaceClient::new("acct", "secret", NAMESPACE_ID).key_pager();
+        let mut requested = Vec::new();
+        let result = futures::executor::block_on(collect_key_pages(pager, |cursor| {
+            requested.push(cursor);
+            async {
+                let mut info = result_info("stale", Some(true));
+                info.count = 1;
+                Ok((key_infos(&["a"]), info))
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(requested, [""]);
+        assert_eq!(result, (vec!["a".to_string()], 1));
+    }
+
+    #[test]
+    fn key_pager_follows_cursor_until_empty() {
+        let mut pager = KvNamespaceClient::new("acct", "secret", NAMESPACE_ID).key_pager();
+        assert_eq!(pager.cursor(), "");
+        assert!(pager.advance("page2".to_string()).unwrap());
+        assert_eq!(pager.cursor(), "page2");
+        assert!(!pager.advance(String::new()).unwrap());
+    }
+
+    #[test]
+    fn key_pager_rejects_repeated_cursor() {
+        let mut pager = KvNamespaceClient::new("acct", "secret", NAMESPACE_ID).key_pager();
+        assert!(pager.advance("page2".to_string()).unwrap());
+        assert!(matches!(
+            pager.advance("page2".to_string()),
+            Err(KvError::Pagination(_))
+        ));
+    }
+
+    #[test]
+    fn key_pager_stops_at_max_pages() {
+        let mut pager = KvNamespaceClient::new("acct", "secret", NAMESPACE_ID)
+            .with_max_pages(2)
+            .key_pager();
+        assert!(pager.advance("page2".to_string()).unwrap());
+        assert!(matches!(
+            pager.advance("page3".to_string()),
+            Err(KvError::Pagination(_))
+        ));
+    }
+
+    fn namespaces(range: std::ops::Range<usize>) -> Vec<Namespace> {
+        range
+            .map(|i| Namespace {
+                id: format!("{:032x}", i),
+                title: format!("ns-{}", i),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn list_namespaces_follows_full_pages() {
+        let mut requested = Vec::new();
+        let result = futures::executor::block_on(collect_namespace_pages(|page| {
+            requested.push(page);
+            async move {
+                Ok(match page {
+                    1 => namespaces(0..NAMESPACES_PER_PAGE),
+                    _ => namespaces(NAMESPACES_PER_PAGE..NAMESPACES_PER_PAGE + 3),
+                })
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(requested, [1, 2]);
+        assert_eq!(result, namespaces(0..NAMESPACES_PER_PAGE + 3));
+    }
+
+    #[test]
+    fn empty_namespace_result_parses_to_empty_vec() {
+        let resp_json = json!({ "success": true, "errors": [], "result": [] });
+        let resp = parse_response::<Vec<Namespace>>(resp_json).unwrap();
+        assert_eq!(resp.result, Vec::<Namespace>::new());
+    }
+
+    #[test]
+    fn list_namespaces_stops_on_short_page() {
+        let mut requested = Vec::new();
+        let result = futures::executor::block_on(collect_namespace_pages(|page| {
+            requested.push(page);
+            async { Ok(Vec::new()) }
+        }))
+        .unwrap();
+
+        assert_eq!(requested, [1]);
+        assert!(result.is_empty());
+    }
+
+    fn decoded_value(request: &KvRequest) -> Vec<u8> {
+        assert!(request.base64);
+        base64::engine::general_purpose::STANDARD
+            .decode(&request.value)
+            .unwrap()
+    }
+
+    #[test]
+    fn raw_bytes_round_trip_through_base64() {
+        let bytes = (0..=255u8).chain([0, 0xff, 0x80]).collect::<Vec<_>>();
+
+        let request = KvRequest::from_bytes("key", &bytes);
+        assert_eq!(decoded_value(&request), bytes);
+
+        let request = KvRequest::new("key", "text").with_raw_bytes(&bytes);
+        assert_eq!(decoded_value(&request), bytes);
+
+        let request = KvRequest::from_bytes("key", &bytes).with_write_options(WriteOptions {
+            expiration_ttl: Some(120),
+            metadata: Some(json!({ "kind": "blob" })),
+            ..WriteOptions::default()
+        });
+        assert_eq!(decoded_value(&request), bytes);
+        assert_eq!(request.expiration_ttl, Some(120));
+        assert_eq!(request.value_len(), bytes.len());
+        assert!(request.validate().is_ok());
+
+        assert_eq!(
+            decoded_value(&KvRequest::from_bytes("key", &[])),
+            Vec::<u8>::new()
+        );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn utc_date_formats_known_days() {
+        assert_eq!(utc_date(0), "1970-01-01");
+        assert_eq!(utc_date(951_782_400), "2000-02-29");
+        assert_eq!(utc_date(1_735_689_600), "2025-01-01");
+    }
+
+    #[test]
+    fn try_new_rejects_invalid_namespace_id() {
+        assert!(KvNamespaceClient::try_new("acct", "secret", "not-a-namespace-id").is_err());
+        assert!(KvNamespaceClient::try_new("acct", "secret", NAMESPACE_ID).is_ok());
+    }
 }
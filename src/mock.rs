@@ -0,0 +1,61 @@
+use crate::{KvError, KvRequest, KvStore};
+use base64::Engine;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// In-memory `KvStore` for tests, backed by a `HashMap`.
+///
+/// Writes are validated like real ones, but expirations and metadata are not kept.
+#[derive(Debug, Default)]
+pub struct MockKvStore {
+    values: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+impl MockKvStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl KvStore for MockKvStore {
+    async fn get(&self, key: &str) -> Result<String, KvError> {
+        match self.values.lock().unwrap().get(key) {
+            Some(value) => Ok(String::from_utf8_lossy(value).into_owned()),
+            None => Err(KvError::NotFound {
+                key: key.to_string(),
+            }),
+        }
+    }
+
+    async fn write(&self, payload: KvRequest) -> Result<(), KvError> {
+        payload.validate()?;
+
+        let value = if payload.base64 {
+            base64::engine::general_purpose::STANDARD
+                .decode(&payload.value)
+                .map_err(|e| KvError::InvalidRequest(e.to_string()))?
+        } else {
+            payload.value.into_bytes()
+        };
+
+        self.values.lock().unwrap().insert(payload.key, value);
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), KvError> {
+        self.values.lock().unwrap().remove(key);
+        Ok(())
+    }
+
+    async fn list_all_keys(&self) -> Result<Vec<String>, KvError> {
+        let mut keys = self
+            .values
+            .lock()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        keys.sort();
+        Ok(keys)
+    }
+}